@@ -0,0 +1,34 @@
+use serde::Serialize;
+use std::{fs::OpenOptions, io::Write};
+
+use crate::error::WalletError;
+
+/// One unlock step attempt, stored as a line of JSON in the `--audit-log`.
+#[derive(Serialize, Debug)]
+pub struct Entry {
+    pub timestamp: i64,
+    pub owner: String,
+    pub fee_payer: String,
+    pub step: String,
+    pub timelock: String,
+    pub unlock_pda: String,
+    /// "sent" once broadcast, then one of "confirmed", "simulated", "skipped"
+    /// or "failed"
+    pub outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Appends an entry as a single write and syncs it to disk before
+/// returning. The file is only ever appended to.
+pub fn append(path: &str, entry: &Entry) -> Result<(), WalletError> {
+    let line = serde_json::to_string(entry)
+        .map_err(|e| WalletError::Config(format!("failed to serialize audit log entry: {}", e)))?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n", line).as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
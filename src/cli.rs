@@ -0,0 +1,397 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+
+use crate::error::WalletError;
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    success
+  1    aborted, some --owners-dir owners or doctor checks failed, or I/O error
+  2    invalid configuration or arguments
+  3    nothing to do: no transaction was needed (already done), or the timelock is still running
+  4    key loading failed
+  5    an account is missing or in an unexpected state
+  6    a transaction could not be built, was rejected or failed
+  7    RPC or fee sponsor error
+  8    timed out waiting for confirmation or the timelock
+  130  interrupted";
+
+/// Command-line overrides. Anything left unset falls back to `wallet.toml`
+/// and then to the built-in mainnet defaults.
+#[derive(Parser, Debug)]
+#[command(version, about = "Unlock timelock tokens held in the Code VM", after_help = EXIT_CODES_HELP)]
+pub struct Args {
+    /// Action to run; without one the full unlock lifecycle runs
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to a TOML wallet config, or "-" for stdin (defaults to wallet.toml if present)
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Read the TOML wallet config from this environment variable instead of a file
+    #[arg(long, conflicts_with = "config")]
+    pub config_env: Option<String>,
+
+    /// Cluster preset supplying default RPC and VM addresses [default: mainnet]
+    #[arg(long, value_parser = ["mainnet", "devnet", "testnet"])]
+    pub network: Option<String>,
+
+    /// Solana RPC endpoint; repeat to add failover endpoints in priority order
+    #[arg(long)]
+    pub rpc_url: Vec<String>,
+
+    /// Commitment level for RPC reads and confirmations [default: confirmed]
+    #[arg(long, value_parser = ["processed", "confirmed", "finalized"])]
+    pub commitment: Option<String>,
+
+    /// Commitment sent transactions must reach before counting as confirmed [default: --commitment]
+    #[arg(long, value_parser = ["processed", "confirmed", "finalized"])]
+    pub commitment_for_send: Option<String>,
+
+    /// Retries for transient RPC failures [default: 3]
+    #[arg(long)]
+    pub max_retries: Option<u32>,
+
+    /// Base delay between RPC retries, doubled on each attempt [default: 500]
+    #[arg(long)]
+    pub retry_delay_ms: Option<u64>,
+
+    /// Timeout in seconds for each individual RPC request (not the whole run)
+    #[arg(long)]
+    pub rpc_timeout: Option<u64>,
+
+    /// Mint of the timelocked token
+    #[arg(long)]
+    pub mint: Option<String>,
+
+    /// VM state account
+    #[arg(long)]
+    pub vm_state: Option<String>,
+
+    /// VM authority
+    #[arg(long)]
+    pub vm_authority: Option<String>,
+
+    /// Lock duration (in days) the timelock was created with
+    #[arg(long)]
+    pub lock_duration: Option<u8>,
+
+    /// Owner keyfile, or "-" to read it from stdin
+    #[arg(long)]
+    pub owner_key: Option<String>,
+
+    /// Sign as the owner with a Ledger key at this derivation path, e.g. "m/44'/501'/0'/0'"
+    #[cfg(feature = "ledger")]
+    #[arg(long, conflicts_with_all = ["owner_key", "owner_mnemonic", "owner_key_env"])]
+    pub owner_ledger: Option<String>,
+
+    /// Read the owner's base58 secret key from this environment variable instead of a keyfile
+    #[arg(long, conflicts_with_all = ["owner_key", "owner_mnemonic"])]
+    pub owner_key_env: Option<String>,
+
+    /// Derive the owner key from a BIP39 mnemonic (m/44'/501'/0'/0') instead of a keyfile
+    #[arg(long, conflicts_with = "owner_key")]
+    pub owner_mnemonic: Option<String>,
+
+    /// Process every keyfile in this directory as a separate owner
+    #[arg(long, conflicts_with_all = ["owner_key", "owner_mnemonic", "owner_key_env"])]
+    pub owners_dir: Option<String>,
+
+    /// Owners processed at once with --owners-dir
+    #[arg(long, default_value_t = 4, requires = "owners_dir")]
+    pub concurrency: usize,
+
+    /// BIP44 path to derive the --owner-mnemonic key at, e.g. "m/44'/501'/1'/0'" or "m/44'/501'/1'";
+    /// "raw" uses the seed directly, like keyfiles saved from the mnemonic prompt
+    #[arg(long, requires = "owner_mnemonic")]
+    pub derivation_path: Option<String>,
+
+    /// BIP39 passphrase used with --owner-mnemonic
+    #[arg(long, requires = "owner_mnemonic")]
+    pub owner_passphrase: Option<String>,
+
+    /// Fee payer keyfile, or "-" to read it from stdin
+    #[arg(long)]
+    pub payer_key: Option<String>,
+
+    /// Read the fee payer's base58 secret key from this environment variable instead of a keyfile
+    #[arg(long, conflicts_with = "payer_key")]
+    pub payer_key_env: Option<String>,
+
+    /// Fee sponsor endpoint that co-signs transactions as fee payer
+    #[arg(long, requires = "sponsor_pubkey", conflicts_with_all = ["payer_key", "payer_key_env"])]
+    pub sponsor_url: Option<String>,
+
+    /// Fee payer pubkey of the sponsor at --sponsor-url
+    #[arg(long, requires = "sponsor_url")]
+    pub sponsor_pubkey: Option<String>,
+
+    /// Read keyfile passphrases from this environment variable instead of prompting
+    #[arg(long)]
+    pub passphrase_env: Option<String>,
+
+    /// Refuse to load keyfiles readable by group or others instead of warning
+    #[arg(long)]
+    pub strict_permissions: bool,
+
+    /// Simulate transactions instead of sending them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Skip the confirmation prompt before sending on mainnet
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Simulate each transaction first and abort without sending if it fails
+    #[arg(long, conflicts_with = "dry_run")]
+    pub simulate_first: bool,
+
+    /// Increase log verbosity (-v debug, -vv trace)
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only log warnings and errors
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Print each instruction's program, accounts with their roles, and data before signing
+    #[arg(long)]
+    pub show_instruction: bool,
+
+    /// Emit a single JSON object on stdout instead of human-readable output
+    #[arg(long)]
+    pub json: bool,
+
+    /// Never color output, even on a terminal
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Block explorer used for transaction links
+    #[arg(long, value_enum, default_value_t = Explorer::Solscan)]
+    pub output_explorer: Explorer,
+
+    /// Priority fee as a compute unit price in micro-lamports
+    #[arg(long, default_value_t = 0)]
+    pub priority_fee: u64,
+
+    /// Abort if a transaction's estimated fee exceeds this many lamports (default 0.01 SOL)
+    #[arg(long, default_value_t = 10_000_000)]
+    pub max_fee_lamports: u64,
+
+    /// Check the fee payer's SOL balance covers the fee and any rent before sending
+    #[arg(long)]
+    pub fee_payer_balance_check: bool,
+
+    /// Compute unit limit to request for each transaction
+    #[arg(long)]
+    pub compute_limit: Option<u32>,
+
+    /// Broadcast without the RPC node simulating the transaction first
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    /// Commitment the RPC node simulates against before broadcasting [default: --commitment]
+    #[arg(long, value_parser = ["processed", "confirmed", "finalized"], conflicts_with = "skip_preflight")]
+    pub preflight_commitment: Option<String>,
+
+    /// Seconds to wait for a sent transaction to confirm
+    #[arg(long, default_value_t = 60)]
+    pub confirm_timeout: u64,
+
+    /// Only report success once sent transactions are finalized, within --confirm-timeout
+    #[arg(long, conflicts_with = "commitment_for_send")]
+    pub await_finalization: bool,
+
+    /// Times to re-sign and resend a transaction whose blockhash expired
+    #[arg(long, default_value_t = 3)]
+    pub max_resends: u32,
+
+    /// Seconds between unlock state checks while waiting out the timelock
+    #[arg(long, default_value_t = 60)]
+    pub watch_interval: u64,
+
+    /// Give up waiting for the timelock after this many seconds
+    #[arg(long)]
+    pub watch_timeout: Option<u64>,
+
+    /// Before finalizing, re-read the unlock state at the send commitment and
+    /// fail if the earlier read is more than this many slots old or no longer holds
+    #[arg(long)]
+    pub max_age_slots: Option<u64>,
+
+    /// Attach an SPL memo, signed by the owner, to each transaction
+    #[arg(long)]
+    pub memo: Option<String>,
+
+    /// Log of confirmed unlock steps; steps already recorded there are skipped
+    #[arg(long)]
+    pub processed_log: Option<String>,
+
+    /// Append a JSON line for every unlock step attempt and its outcome to this file
+    #[arg(long)]
+    pub audit_log: Option<String>,
+
+    /// Address lookup table to compile v0 transactions against
+    #[arg(long)]
+    pub lookup_table: Option<String>,
+
+    /// Durable nonce account to sign against instead of a recent blockhash
+    #[arg(long)]
+    pub nonce_account: Option<String>,
+
+    /// Authority of the nonce account; must be the owner or fee payer [default: fee payer]
+    #[arg(long, requires = "nonce_account")]
+    pub nonce_authority: Option<String>,
+
+    /// Sign against this base58 blockhash instead of fetching the latest one;
+    /// with --nonce-account, the nonce's stored value, used without reading the account
+    #[arg(long)]
+    pub blockhash: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Initiate the timelock unlock and report when it expires, without waiting
+    Unlock,
+
+    /// Finalize an unlock whose timelock has already passed, without waiting
+    Finalize,
+
+    /// Report the unlock state and the next step without sending anything
+    Status,
+
+    /// Check RPC connectivity, the cluster and the VM state account without signing anything
+    Doctor,
+
+    /// Print the derived timelock and unlock PDAs without sending anything
+    Pdas {
+        /// Owner pubkey; defaults to the owner key from --owner-key/--owner-mnemonic
+        #[arg(long)]
+        owner: Option<String>,
+    },
+
+    /// Print the pubkey of a keyfile, or of the owner key, without signing anything
+    ExportPubkey {
+        /// Keyfile to read; defaults to the owner key from --owner-key/--owner-mnemonic
+        #[arg(long)]
+        key: Option<String>,
+
+        /// Also print the key's associated token account for the configured mint
+        #[arg(long)]
+        ata: bool,
+    },
+
+    /// Sign one unlock step and write the transaction to a file without sending it
+    Build {
+        /// Unlock step to sign
+        #[arg(long, value_enum)]
+        step: UnlockStep,
+
+        /// File to write the signed transaction to
+        #[arg(long)]
+        output: String,
+
+        /// Text encoding of the transaction file
+        #[arg(long, value_enum, default_value_t = TxEncoding::Base64)]
+        encoding: TxEncoding,
+
+        /// Skip on-chain state checks before signing
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Broadcast a transaction written by `build` and wait for confirmation
+    Submit {
+        /// Transaction file produced by `build`
+        #[arg(long)]
+        input: String,
+
+        /// Text encoding of the transaction file
+        #[arg(long, value_enum, default_value_t = TxEncoding::Base64)]
+        encoding: TxEncoding,
+    },
+
+    /// Encrypt a plaintext keyfile with a passphrase
+    EncryptKey {
+        /// Plaintext keyfile to encrypt
+        #[arg(long)]
+        input: String,
+
+        /// Where to write the encrypted keyfile; defaults to replacing the input
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// Generate a new keypair and write it to a keyfile
+    GenerateKey {
+        /// Path of the keyfile to create
+        #[arg(long)]
+        output: String,
+
+        /// Keyfile layout to write
+        #[arg(long, value_enum, default_value_t = KeyFormat::Wallet)]
+        format: KeyFormat,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Explorer {
+    Solscan,
+    Solanafm,
+    Explorer,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum UnlockStep {
+    /// timelock_unlock_init, which starts the timelock
+    Init,
+    /// timelock_unlock_finalize, once the timelock has passed
+    Finalize,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum TxEncoding {
+    Base64,
+    Base58,
+}
+
+/// On-disk keyfile layouts understood by `load_keypair_from_file`.
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum KeyFormat {
+    /// `{ "private_key": [32-byte seed], "pubkey": "..." }`
+    Wallet,
+    /// Solana CLI JSON array of the 64-byte keypair
+    Cli,
+}
+
+/// Path meaning "read from stdin" for `--config`, `--owner-key` and `--payer-key`.
+pub const STDIN_PATH: &str = "-";
+
+/// Parses a pubkey argument, naming the offending flag on failure.
+pub fn parse_pubkey(flag: &str, value: &str) -> Result<Pubkey, WalletError> {
+    Pubkey::from_str(value).map_err(|_| WalletError::InvalidPubkey {
+        field: flag.to_string(),
+        value: value.to_string(),
+    })
+}
+
+pub fn parse_commitment(value: &str) -> Result<CommitmentConfig, WalletError> {
+    match value {
+        "processed" => Ok(CommitmentConfig { commitment: CommitmentLevel::Processed }),
+        "confirmed" => Ok(CommitmentConfig { commitment: CommitmentLevel::Confirmed }),
+        "finalized" => Ok(CommitmentConfig { commitment: CommitmentLevel::Finalized }),
+        other => Err(WalletError::Config(format!(
+            "unknown commitment '{}', expected processed, confirmed or finalized",
+            other
+        ))),
+    }
+}
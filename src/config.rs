@@ -0,0 +1,328 @@
+use serde::Deserialize;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::cli::{parse_commitment, parse_pubkey, Args, Command, STDIN_PATH};
+use crate::error::WalletError;
+use crate::networks::{self, NetworkPreset, DEFAULT_NETWORK};
+use crate::LOCK_DURATION;
+
+pub const DEFAULT_CONFIG_PATH: &str = "wallet.toml";
+
+/// Resolved wallet settings: network preset, then `wallet.toml`, then CLI flags.
+#[derive(Debug, Clone)]
+pub struct WalletConfig {
+    pub network: String,
+    pub rpc_url: String,
+    pub commitment: String,
+    /// Commitment a sent transaction must reach; defaults to `commitment`
+    pub send_commitment: Option<String>,
+    pub max_retries: u32,
+    pub retry_delay_ms: u64,
+    /// Per-request RPC timeout in seconds; the client default when unset
+    pub rpc_timeout: Option<u64>,
+    pub mint: String,
+    pub vm_state: String,
+    pub vm_authority: String,
+    pub lock_duration: u8,
+    pub owner_key_path: String,
+    pub payer_key_path: String,
+}
+
+/// On-disk form of `WalletConfig`; every field is optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    network: Option<String>,
+    rpc_url: Option<String>,
+    commitment: Option<String>,
+    send_commitment: Option<String>,
+    max_retries: Option<u32>,
+    retry_delay_ms: Option<u64>,
+    rpc_timeout: Option<u64>,
+    mint: Option<String>,
+    vm_state: Option<String>,
+    vm_authority: Option<String>,
+    lock_duration: Option<u8>,
+    owner_key_path: Option<String>,
+    payer_key_path: Option<String>,
+}
+
+impl Default for WalletConfig {
+    fn default() -> Self {
+        Self::for_network(networks::lookup(DEFAULT_NETWORK).expect("default network preset"))
+    }
+}
+
+impl WalletConfig {
+    pub fn for_network(preset: &NetworkPreset) -> Self {
+        Self {
+            network: preset.name.to_string(),
+            rpc_url: preset.rpc_url.to_string(),
+            commitment: "confirmed".to_string(),
+            send_commitment: None,
+            max_retries: 3,
+            retry_delay_ms: 500,
+            rpc_timeout: None,
+            mint: preset.mint.unwrap_or_default().to_string(),
+            vm_state: preset.vm_state.unwrap_or_default().to_string(),
+            vm_authority: preset.vm_authority.unwrap_or_default().to_string(),
+            lock_duration: LOCK_DURATION,
+            owner_key_path: "owner_key.json".to_string(),
+            payer_key_path: "payer_key.json".to_string(),
+        }
+    }
+
+    /// Loads the config named on the command line (from a file, stdin or an
+    /// environment variable; `wallet.toml` if present otherwise) on top of
+    /// the selected network preset, then applies any CLI overrides.
+    pub fn resolve(args: &Args) -> Result<Self, WalletError> {
+        let config_from_stdin = args.config.as_deref() == Some(STDIN_PATH);
+        let file = match (&args.config, &args.config_env) {
+            (Some(_), _) if config_from_stdin => {
+                let mut content = String::new();
+                io::stdin()
+                    .lock()
+                    .read_to_string(&mut content)
+                    .map_err(|e| WalletError::Config(format!("failed to read config from stdin: {}", e)))?;
+                parse_config("<stdin>", &content)?
+            }
+            (Some(path), _) => read_config_file(Path::new(path))?,
+            (None, Some(var)) => {
+                let content = std::env::var(var)
+                    .map_err(|e| WalletError::Config(format!("failed to read config from ${}: {}", var, e)))?;
+                parse_config(&format!("${}", var), &content)?
+            }
+            (None, None) if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                read_config_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            (None, None) => ConfigFile::default(),
+        };
+
+        let network = args.network.as_deref()
+            .or(file.network.as_deref())
+            .unwrap_or(DEFAULT_NETWORK)
+            .to_string();
+        let mut config = Self::for_network(find_network(&network)?);
+        config.apply_file(file);
+        config.network = network;
+        config.apply_args(args);
+        config.validate()?;
+        config.check_stdin(args, config_from_stdin)?;
+        Ok(config)
+    }
+
+    /// Stdin can only be read once, so at most one input or prompt may
+    /// claim it.
+    fn check_stdin(&self, args: &Args, config_from_stdin: bool) -> Result<(), WalletError> {
+        let owner_keyfile = reads_owner_keyfile(args);
+        let sends = matches!(args.command, Some(Command::Unlock) | Some(Command::Finalize) | None);
+        let submits = matches!(args.command, Some(Command::Submit { .. }));
+        let claims: Vec<&str> = [
+            ("--config", config_from_stdin),
+            ("the owner key", owner_keyfile && self.owner_key_path == STDIN_PATH),
+            (
+                "the mnemonic prompt for the missing owner keyfile",
+                owner_keyfile && self.owner_key_path != STDIN_PATH && !Path::new(&self.owner_key_path).exists(),
+            ),
+            ("the payer key", reads_payer_keyfile(args) && self.payer_key_path == STDIN_PATH),
+            (
+                "export-pubkey --key",
+                matches!(&args.command, Some(Command::ExportPubkey { key: Some(key), .. }) if key == STDIN_PATH),
+            ),
+            (
+                "the mainnet confirmation prompt",
+                self.network == "mainnet" && !args.yes && (submits || (sends && !args.dry_run)),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(input, claimed)| claimed.then_some(input))
+        .collect();
+        if claims.len() > 1 {
+            return Err(WalletError::Config(format!(
+                "only one input can be read from stdin, but {} all ask for it",
+                claims.join(" and ")
+            )));
+        }
+        Ok(())
+    }
+
+    fn apply_file(&mut self, file: ConfigFile) {
+        if let Some(rpc_url) = file.rpc_url {
+            self.rpc_url = rpc_url;
+        }
+        if let Some(commitment) = file.commitment {
+            self.commitment = commitment;
+        }
+        if let Some(commitment) = file.send_commitment {
+            self.send_commitment = Some(commitment);
+        }
+        if let Some(max_retries) = file.max_retries {
+            self.max_retries = max_retries;
+        }
+        if let Some(retry_delay_ms) = file.retry_delay_ms {
+            self.retry_delay_ms = retry_delay_ms;
+        }
+        if let Some(rpc_timeout) = file.rpc_timeout {
+            self.rpc_timeout = Some(rpc_timeout);
+        }
+        if let Some(mint) = file.mint {
+            self.mint = mint;
+        }
+        if let Some(vm_state) = file.vm_state {
+            self.vm_state = vm_state;
+        }
+        if let Some(vm_authority) = file.vm_authority {
+            self.vm_authority = vm_authority;
+        }
+        if let Some(lock_duration) = file.lock_duration {
+            self.lock_duration = lock_duration;
+        }
+        if let Some(path) = file.owner_key_path {
+            self.owner_key_path = path;
+        }
+        if let Some(path) = file.payer_key_path {
+            self.payer_key_path = path;
+        }
+    }
+
+    pub fn apply_args(&mut self, args: &Args) {
+        if !args.rpc_url.is_empty() {
+            self.rpc_url = args.rpc_url.join(",");
+        }
+        if let Some(commitment) = &args.commitment {
+            self.commitment = commitment.clone();
+        }
+        if let Some(commitment) = &args.commitment_for_send {
+            self.send_commitment = Some(commitment.clone());
+        }
+        if let Some(max_retries) = args.max_retries {
+            self.max_retries = max_retries;
+        }
+        if let Some(retry_delay_ms) = args.retry_delay_ms {
+            self.retry_delay_ms = retry_delay_ms;
+        }
+        if let Some(rpc_timeout) = args.rpc_timeout {
+            self.rpc_timeout = Some(rpc_timeout);
+        }
+        if let Some(mint) = &args.mint {
+            self.mint = mint.clone();
+        }
+        if let Some(vm_state) = &args.vm_state {
+            self.vm_state = vm_state.clone();
+        }
+        if let Some(vm_authority) = &args.vm_authority {
+            self.vm_authority = vm_authority.clone();
+        }
+        if let Some(lock_duration) = args.lock_duration {
+            self.lock_duration = lock_duration;
+        }
+        if let Some(path) = &args.owner_key {
+            self.owner_key_path = path.clone();
+        }
+        if let Some(path) = &args.payer_key {
+            self.payer_key_path = path.clone();
+        }
+    }
+
+    /// RPC endpoints in priority order; `rpc_url` may hold a comma-separated list.
+    pub fn rpc_urls(&self) -> Vec<String> {
+        self.rpc_url
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub fn commitment_config(&self) -> Result<CommitmentConfig, WalletError> {
+        parse_commitment(&self.commitment)
+    }
+
+    pub fn send_commitment_config(&self) -> Result<CommitmentConfig, WalletError> {
+        parse_commitment(self.send_commitment.as_deref().unwrap_or(&self.commitment))
+    }
+
+    /// Checks every pubkey field and the commitments, reporting all bad values at once.
+    pub fn validate(&self) -> Result<(), WalletError> {
+        let mut errors: Vec<String> = [
+            ("mint", &self.mint),
+            ("vm-state", &self.vm_state),
+            ("vm-authority", &self.vm_authority),
+        ]
+        .iter()
+        .filter_map(|(field, value)| {
+            if value.is_empty() {
+                Some(format!("--{} is required for network '{}'", field, self.network))
+            } else {
+                parse_pubkey(field, value).err().map(|e| e.to_string())
+            }
+        })
+        .collect();
+        if self.rpc_urls().is_empty() {
+            errors.push("at least one RPC URL is required".to_string());
+        }
+        if let Err(e) = self.commitment_config() {
+            errors.push(e.to_string());
+        }
+        if let Some(Err(e)) = self.send_commitment.as_deref().map(parse_commitment) {
+            errors.push(e.to_string());
+        }
+
+        if !errors.is_empty() {
+            return Err(WalletError::Config(format!("\n  {}", errors.join("\n  "))));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the command loads the owner from its keyfile: it needs the
+/// owner and no ledger, environment variable or mnemonic replaces the file.
+fn reads_owner_keyfile(args: &Args) -> bool {
+    let needs_owner = match &args.command {
+        Some(Command::Pdas { owner }) => owner.is_none(),
+        Some(Command::ExportPubkey { key, .. }) => key.is_none(),
+        Some(Command::Unlock) | Some(Command::Finalize) | Some(Command::Build { .. }) | Some(Command::Status) | None => {
+            args.owners_dir.is_none()
+        }
+        _ => false,
+    };
+    #[cfg(feature = "ledger")]
+    if args.owner_ledger.is_some() {
+        return false;
+    }
+    needs_owner && args.owner_key_env.is_none() && args.owner_mnemonic.is_none()
+}
+
+/// Whether the command loads the fee payer from its keyfile: it signs
+/// transactions and neither a sponsor nor an environment variable pays.
+fn reads_payer_keyfile(args: &Args) -> bool {
+    let signs = matches!(
+        args.command,
+        Some(Command::Unlock) | Some(Command::Finalize) | Some(Command::Build { .. }) | None
+    );
+    signs && args.sponsor_url.is_none() && args.payer_key_env.is_none()
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, WalletError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| WalletError::Config(format!("failed to read {}: {}", path.display(), e)))?;
+    parse_config(&path.display().to_string(), &content)
+}
+
+/// Parses TOML config text; `source` names where it came from in errors.
+fn parse_config(source: &str, content: &str) -> Result<ConfigFile, WalletError> {
+    toml::from_str(content)
+        .map_err(|e| WalletError::Config(format!("failed to parse {}: {}", source, e)))
+}
+
+fn find_network(name: &str) -> Result<&'static NetworkPreset, WalletError> {
+    networks::lookup(name).ok_or_else(|| {
+        let known: Vec<&str> = networks::NETWORKS.iter().map(|preset| preset.name).collect();
+        WalletError::Config(format!("unknown network '{}', expected one of: {}", name, known.join(", ")))
+    })
+}
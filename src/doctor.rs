@@ -0,0 +1,133 @@
+use code_vm_api::prelude::*;
+use log::info;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::cli::parse_pubkey;
+use crate::config::WalletConfig;
+use crate::error::WalletError;
+use crate::networks;
+use crate::output;
+use crate::rpc::VmRpc;
+use crate::{check_account_type, rpc_pool, VM_PROGRAM_ID};
+
+/// One read-only check run by `doctor`.
+#[derive(Serialize, Debug)]
+pub struct Check {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Everything `doctor` found; with `--json` it is the only output, failed
+/// checks included.
+#[derive(Serialize, Debug)]
+pub struct DoctorReport {
+    pub network: String,
+    /// Whether every check passed
+    pub ok: bool,
+    pub failed: usize,
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    fn record(&mut self, name: impl Into<String>, result: Result<String, String>) {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        self.checks.push(Check { name: name.into(), ok, detail });
+    }
+}
+
+/// Checks every configured RPC endpoint (health, version, cluster) and then
+/// the VM state account, without loading keys or sending anything. Every
+/// check runs even after an earlier one fails, so one run shows all problems.
+pub async fn run(config: &WalletConfig) -> Result<(), WalletError> {
+    let mut report = DoctorReport {
+        network: config.network.clone(),
+        ok: false,
+        failed: 0,
+        checks: Vec::new(),
+    };
+    let expected_genesis = networks::lookup(&config.network).and_then(|preset| preset.genesis_hash);
+
+    for url in config.rpc_urls() {
+        // One endpoint per pool, so failover cannot hide a dead endpoint.
+        let endpoint = WalletConfig { rpc_url: url.clone(), ..config.clone() };
+        let rpc = rpc_pool(&endpoint)?;
+
+        report.record(
+            format!("{} health", url),
+            rpc.get_health().await.map(|()| "ok".to_string()).map_err(|e| e.to_string()),
+        );
+        report.record(
+            format!("{} version", url),
+            rpc.get_version().await.map(|version| version.solana_core).map_err(|e| e.to_string()),
+        );
+        let cluster = match rpc.get_genesis_hash().await {
+            Ok(hash) => match expected_genesis {
+                Some(expected) if hash.to_string() != expected => Err(format!(
+                    "genesis hash {} is not {}'s {}",
+                    hash, config.network, expected
+                )),
+                Some(_) => Ok(format!("genesis hash {} matches {}", hash, config.network)),
+                None => Ok(format!("genesis hash {} (no known hash for {})", hash, config.network)),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+        report.record(format!("{} cluster", url), cluster);
+    }
+
+    let rpc = rpc_pool(config)?;
+    report.record("vm state account", check_vm_state(&rpc, config).await);
+
+    let failed = report.checks.iter().filter(|check| !check.ok).count();
+    report.failed = failed;
+    report.ok = failed == 0;
+    if output::json_mode() {
+        output::print_json(&report);
+    } else {
+        for check in &report.checks {
+            info!("[{}] {}: {}", if check.ok { "pass" } else { "FAIL" }, check.name, check.detail);
+        }
+    }
+
+    if failed > 0 {
+        return Err(WalletError::ChecksFailed { failed, total: report.checks.len() });
+    }
+    Ok(())
+}
+
+/// Reads `--vm-state`, checks it is a VM account of the right size and that
+/// its lock duration matches the configured one.
+async fn check_vm_state(rpc: &dyn VmRpc, config: &WalletConfig) -> Result<String, String> {
+    let address = parse_pubkey("vm-state", &config.vm_state).map_err(|e| e.to_string())?;
+    let program_id = Pubkey::from_str(VM_PROGRAM_ID).expect("valid VM program id");
+
+    let account = rpc
+        .get_account(&address)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("{} does not exist", address))?;
+    if account.owner != program_id {
+        return Err(format!("{} is owned by {}, expected the VM program {}", address, account.owner, program_id));
+    }
+    if account.data.len() < CodeVmAccount::get_size() {
+        return Err(format!(
+            "{} has {} bytes of data, expected at least {}",
+            address, account.data.len(), CodeVmAccount::get_size()
+        ));
+    }
+    check_account_type(&address, &account.data, AccountType::CodeVmAccount, "VM state").map_err(|e| e.to_string())?;
+
+    let vm = CodeVmAccount::unpack(&account.data);
+    if vm.lock_duration != config.lock_duration {
+        return Err(format!(
+            "VM lock duration is {} days, configured {}",
+            vm.lock_duration, config.lock_duration
+        ));
+    }
+    Ok(format!("{} unpacks, lock duration {} days", address, vm.lock_duration))
+}
@@ -0,0 +1,190 @@
+use solana_client::client_error::ClientError;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WalletError {
+    #[error("Invalid pubkey for --{field}: '{value}'")]
+    InvalidPubkey { field: String, value: String },
+
+    #[error("Invalid wallet config: {0}")]
+    Config(String),
+
+    #[error("Failed to load keyfile {path}: {reason}")]
+    KeyFileParse { path: String, reason: String },
+
+    #[error("Invalid mnemonic: {0}")]
+    Mnemonic(String),
+
+    #[error("Account not found: {0}")]
+    AccountNotFound(Pubkey),
+
+    #[error("Invalid data in account {account}: {reason}")]
+    InvalidAccountData { account: Pubkey, reason: String },
+
+    #[error("Invalid unlock state")]
+    InvalidUnlockState,
+
+    #[error("Timelock still active until {unlock_at}; nothing to finalize yet")]
+    NotUnlocked { unlock_at: i64 },
+
+    #[error("PDA verification failed for {0}")]
+    PdaVerificationFailed(Pubkey),
+
+    #[error("{label} simulation failed: {reason}")]
+    SimulationFailed { label: String, reason: String },
+
+    #[error("Transaction {signature} was not confirmed before the timeout")]
+    ConfirmationTimeout { signature: Signature },
+
+    #[error("Transaction {signature} failed: {reason}")]
+    TransactionFailed { signature: Signature, reason: String },
+
+    #[error("Blockhash expired before transaction {signature} confirmed")]
+    BlockhashExpired { signature: Signature },
+
+    #[error("Keyfile pubkey mismatch: file says {expected}, private key derives {actual}")]
+    KeyMismatch { expected: String, actual: Pubkey },
+
+    #[error("Keyfile {path} has permissions {mode:o}; restrict it to 0600 (chmod 600 {path})")]
+    InsecureKeyFile { path: String, mode: u32 },
+
+    #[error("Aborted by user")]
+    Aborted,
+
+    #[error("Failed to build transaction: {0}")]
+    TransactionBuild(String),
+
+    #[error("Account {account} has {actual_len} bytes of data, expected at least {expected_len}; is it the right account?")]
+    AccountLayout { account: Pubkey, expected_len: usize, actual_len: usize },
+
+    #[error("Unlock account {0} was closed while waiting; the unlock was cancelled")]
+    UnlockCancelled(Pubkey),
+
+    #[error("Gave up waiting for the timelock after {0:?}")]
+    WatchTimeout(std::time::Duration),
+
+    #[error("Failed to read keypair from environment variable {var}: {reason}")]
+    KeyEnv { var: String, reason: String },
+
+    #[cfg(feature = "ledger")]
+    #[error("Hardware wallet error: {0}")]
+    RemoteSigner(String),
+
+    #[error("Estimated fee of {estimated} lamports exceeds the --max-fee-lamports cap of {cap}")]
+    FeeTooHigh { estimated: u64, cap: u64 },
+
+    #[error("Configured lock duration of {configured} days does not match the VM's {actual} days; pass --lock-duration {actual}")]
+    LockDurationMismatch { configured: u8, actual: u8 },
+
+    #[error("Fee sponsor error: {0}")]
+    Sponsor(String),
+
+    #[error("{failed} of {total} owners failed")]
+    BatchFailed { failed: usize, total: usize },
+
+    #[error("Interrupted by user")]
+    Interrupted,
+
+    #[error("{failed} of {total} checks failed")]
+    ChecksFailed { failed: usize, total: usize },
+
+    #[error("Fee payer {payer} has {have} lamports but needs {need} for fees and rent")]
+    InsufficientFeePayerBalance { payer: Pubkey, have: u64, need: u64 },
+
+    #[error("Unlock state {account} read at slot {read_slot} is stale: {reason}")]
+    StaleState { account: Pubkey, read_slot: u64, reason: String },
+
+    #[error("RPC error: {0}")]
+    Rpc(#[from] ClientError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Exit code for a run that succeeded without needing to send anything, and
+/// for `NotUnlocked`, where nothing can be sent yet.
+pub const EXIT_NOTHING_TO_DO: i32 = 3;
+
+impl WalletError {
+    /// Process exit code for this error, so scripts can branch on the failure type.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            WalletError::InvalidPubkey { .. } | WalletError::Config(_) => 2,
+            WalletError::KeyFileParse { .. } | WalletError::Mnemonic(_) => 4,
+            WalletError::AccountNotFound(_)
+            | WalletError::InvalidAccountData { .. }
+            | WalletError::InvalidUnlockState
+            | WalletError::PdaVerificationFailed(_) => 5,
+            WalletError::NotUnlocked { .. } => EXIT_NOTHING_TO_DO,
+            WalletError::SimulationFailed { .. } | WalletError::TransactionFailed { .. } => 6,
+            WalletError::ConfirmationTimeout { .. } => 8,
+            WalletError::BlockhashExpired { .. } => 8,
+            WalletError::KeyMismatch { .. } => 4,
+            WalletError::InsecureKeyFile { .. } => 4,
+            WalletError::Aborted => 1,
+            WalletError::TransactionBuild(_) => 6,
+            WalletError::AccountLayout { .. } => 5,
+            WalletError::UnlockCancelled(_) => 5,
+            WalletError::WatchTimeout(_) => 8,
+            WalletError::KeyEnv { .. } => 4,
+            #[cfg(feature = "ledger")]
+            WalletError::RemoteSigner(_) => 4,
+            WalletError::FeeTooHigh { .. } => 6,
+            WalletError::LockDurationMismatch { .. } => 2,
+            WalletError::Sponsor(_) => 7,
+            WalletError::BatchFailed { .. } => 1,
+            WalletError::Interrupted => 130,
+            WalletError::ChecksFailed { .. } => 1,
+            WalletError::InsufficientFeePayerBalance { .. } => 6,
+            WalletError::StaleState { .. } => 5,
+            WalletError::Rpc(_) => 7,
+            WalletError::Io(_) => 1,
+        }
+    }
+
+    /// Whether the command already printed its own report, failures
+    /// included, so `--json` output must not add an error object after it.
+    pub fn has_json_report(&self) -> bool {
+        matches!(self, WalletError::BatchFailed { .. } | WalletError::ChecksFailed { .. })
+    }
+
+    /// Stable, machine-readable name of the error variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WalletError::InvalidPubkey { .. } => "invalid_pubkey",
+            WalletError::Config(_) => "config",
+            WalletError::KeyFileParse { .. } => "key_file_parse",
+            WalletError::Mnemonic(_) => "mnemonic",
+            WalletError::AccountNotFound(_) => "account_not_found",
+            WalletError::InvalidAccountData { .. } => "invalid_account_data",
+            WalletError::InvalidUnlockState => "invalid_unlock_state",
+            WalletError::NotUnlocked { .. } => "not_unlocked",
+            WalletError::PdaVerificationFailed(_) => "pda_verification_failed",
+            WalletError::SimulationFailed { .. } => "simulation_failed",
+            WalletError::ConfirmationTimeout { .. } => "confirmation_timeout",
+            WalletError::TransactionFailed { .. } => "transaction_failed",
+            WalletError::BlockhashExpired { .. } => "blockhash_expired",
+            WalletError::KeyMismatch { .. } => "key_mismatch",
+            WalletError::InsecureKeyFile { .. } => "insecure_keyfile",
+            WalletError::Aborted => "aborted",
+            WalletError::TransactionBuild(_) => "transaction_build",
+            WalletError::AccountLayout { .. } => "account_layout",
+            WalletError::UnlockCancelled(_) => "unlock_cancelled",
+            WalletError::WatchTimeout(_) => "watch_timeout",
+            WalletError::KeyEnv { .. } => "key_env",
+            #[cfg(feature = "ledger")]
+            WalletError::RemoteSigner(_) => "remote_signer",
+            WalletError::FeeTooHigh { .. } => "fee_too_high",
+            WalletError::LockDurationMismatch { .. } => "lock_duration_mismatch",
+            WalletError::Sponsor(_) => "sponsor",
+            WalletError::BatchFailed { .. } => "batch",
+            WalletError::Interrupted => "interrupted",
+            WalletError::ChecksFailed { .. } => "checks_failed",
+            WalletError::InsufficientFeePayerBalance { .. } => "insufficient_fee_payer_balance",
+            WalletError::StaleState { .. } => "stale_state",
+            WalletError::Rpc(_) => "rpc",
+            WalletError::Io(_) => "io",
+        }
+    }
+}
@@ -0,0 +1,567 @@
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use bip39::Mnemonic;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    pubkey::Pubkey,
+    signature::{Keypair, SeedDerivable},
+    signer::Signer,
+};
+use std::{
+    fs,
+    io::{self, Read, Write},
+};
+
+use crate::cli::{KeyFormat, STDIN_PATH};
+use crate::error::WalletError;
+
+/// `--derivation-path` value that takes the key straight from the first 32
+/// bytes of the BIP39 seed, as keyfiles saved from the mnemonic prompt do.
+pub const RAW_SEED_PATH: &str = "raw";
+const DEFAULT_DERIVATION_PATH: &str = "m/44'/501'/0'/0'";
+
+// scrypt cost parameters for newly encrypted keyfiles (N = 2^15).
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// This wallet's keyfile: the 32-byte seed and the pubkey it must derive.
+#[derive(Serialize, Deserialize)]
+pub struct KeyFileFormat {
+    #[serde(with = "serde_bytes")]
+    private_key: Vec<u8>,
+    pubkey: String,
+}
+
+impl KeyFileFormat {
+    pub fn new(keypair: &Keypair) -> Self {
+        Self {
+            private_key: keypair.secret().to_bytes().to_vec(),
+            pubkey: keypair.pubkey().to_string(),
+        }
+    }
+
+    /// The pubkey the file claims, before the seed is checked against it.
+    pub fn pubkey(&self) -> &str {
+        &self.pubkey
+    }
+
+    /// The keypair from the stored seed, checked against the stored pubkey.
+    pub fn keypair(&self) -> Result<Keypair, WalletError> {
+        let key_error = |reason: String| WalletError::KeyFileParse {
+            path: "<keyfile>".to_string(),
+            reason,
+        };
+        keypair_from_stored_seed(self.private_key.clone(), self.pubkey.clone(), &key_error)
+    }
+}
+
+/// Derives the owner keypair from a 12-word mnemonic and saves it to `path`
+/// in `KeyFileFormat`.
+pub fn save_mnemonic_keypair(path: &str, phrase: &str) -> Result<(), WalletError> {
+    let phrase = phrase.trim();
+
+    // Validate mnemonic
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if words.len() != 12 {
+        return Err(WalletError::Mnemonic("must be exactly 12 words".to_string()));
+    }
+    
+    if phrase.chars().any(|c| !c.is_ascii_lowercase() && !c.is_whitespace()) {
+        return Err(WalletError::Mnemonic("can only contain lowercase letters and spaces".to_string()));
+    }
+
+    // Keyfiles set up from the prompt have always held the raw seed key, so
+    // it stays the derivation here; `--derivation-path raw` reproduces it.
+    let keypair = load_keypair_from_mnemonic(phrase, "", Some(RAW_SEED_PATH))?;
+
+    // Format and save keypair
+    write_private_file(path, &format_keyfile(&keypair), true)?;
+    log::info!(
+        "Keypair {} saved to {}; pass --derivation-path {} with --owner-mnemonic to derive the same key",
+        keypair.pubkey(), path, RAW_SEED_PATH
+    );
+    Ok(())
+}
+
+/// Renders a keypair in `KeyFileFormat`: the 32-byte seed plus its pubkey.
+fn format_keyfile(keypair: &Keypair) -> String {
+    let private_key_str = keypair
+        .secret()
+        .to_bytes()
+        .iter()
+        .map(|num| num.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    format!(
+        "{{\n    \"private_key\": [{}],\n    \"pubkey\": \"{}\"\n}}",
+        private_key_str,
+        keypair.pubkey()
+    )
+}
+
+/// Generates a fresh keypair and writes it to `path`, readable only by the
+/// current user. Refuses to replace an existing file unless `force` is set.
+pub fn generate_keyfile(path: &str, format: KeyFormat, force: bool) -> Result<Pubkey, WalletError> {
+    let keypair = Keypair::new();
+    let contents = match format {
+        KeyFormat::Wallet => format_keyfile(&keypair),
+        KeyFormat::Cli => serde_json::to_string(&keypair.to_bytes().to_vec())
+            .map_err(|e| WalletError::Config(e.to_string()))?,
+    };
+
+    write_private_file(path, &contents, force)?;
+    Ok(keypair.pubkey())
+}
+
+/// A keyfile whose seed is encrypted with AES-256-GCM under a scrypt-derived key.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedKeyFile {
+    pub pubkey: String,
+    kdf: ScryptParams,
+    #[serde(with = "serde_bytes")]
+    nonce: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScryptParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    #[serde(with = "serde_bytes")]
+    salt: Vec<u8>,
+}
+
+/// Writes `contents` readable only by the current user (0600 on Unix). The
+/// mode is also set on files that already exist, which `--force` and the
+/// reused `.tmp` of `encrypt-key` truncate rather than create.
+fn write_private_file(path: &str, contents: &str, force: bool) -> Result<(), WalletError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => WalletError::Config(format!(
+            "{} already exists, pass --force to overwrite it",
+            path
+        )),
+        _ => WalletError::Io(e),
+    })?;
+    // Tighten before writing so the seed is never readable by others.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// How keyfiles are opened: where encrypted files get their passphrase and
+/// whether loose permissions are fatal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyLoadOptions<'a> {
+    pub passphrase_env: Option<&'a str>,
+    pub strict_permissions: bool,
+}
+
+/// Loads a keypair from a keyfile, or from stdin when `path` is "-".
+pub fn load_keypair_from_file(path: &str, options: &KeyLoadOptions) -> Result<Keypair, WalletError> {
+    if path == STDIN_PATH {
+        return load_keypair_from_reader(io::stdin().lock(), "<stdin>", options);
+    }
+    let key_error = |e: io::Error| WalletError::KeyFileParse {
+        path: path.to_string(),
+        reason: e.to_string(),
+    };
+    check_permissions(path, options.strict_permissions).map_err(|e| match e {
+        WalletError::Io(e) => key_error(e),
+        e => e,
+    })?;
+    let file = fs::File::open(path).map_err(key_error)?;
+    load_keypair_from_reader(file, path, options)
+}
+
+/// Loads a keypair from the Solana CLI format (a JSON array of the 64-byte
+/// keypair), an `EncryptedKeyFile`, or our own `KeyFileFormat` holding the
+/// 32-byte seed. `source` names the input in errors and prompts. Encrypted
+/// keys take their passphrase from `options.passphrase_env` when set,
+/// otherwise from an interactive prompt.
+pub fn load_keypair_from_reader(mut reader: impl Read, source: &str, options: &KeyLoadOptions) -> Result<Keypair, WalletError> {
+    let key_error = |reason: String| WalletError::KeyFileParse {
+        path: source.to_string(),
+        reason,
+    };
+    let mut file_content = String::new();
+    reader.read_to_string(&mut file_content).map_err(|e| key_error(e.to_string()))?;
+
+    if let Ok(bytes) = serde_json::from_str::<Vec<u8>>(&file_content) {
+        if bytes.len() != 64 {
+            return Err(key_error(format!("expected a 64-byte keypair array, found {} bytes", bytes.len())));
+        }
+        return Keypair::from_bytes(&bytes).map_err(|e| key_error(e.to_string()));
+    }
+
+    if let Ok(encrypted) = serde_json::from_str::<EncryptedKeyFile>(&file_content) {
+        let passphrase = read_passphrase(options.passphrase_env, &format!("Passphrase for {}: ", source), false)?;
+        let seed = decrypt_seed(&encrypted, &passphrase).map_err(key_error)?;
+        return keypair_from_stored_seed(seed, encrypted.pubkey, &key_error);
+    }
+
+    let stored: KeyFileFormat = serde_json::from_str(&file_content)
+        .map_err(|e| key_error(e.to_string()))?;
+    keypair_from_stored_seed(stored.private_key, stored.pubkey, &key_error)
+}
+
+/// Keyfiles hold raw seeds, so like ssh we complain when group or other can
+/// read them. Only warns unless `strict` is set.
+#[cfg(unix)]
+fn check_permissions(path: &str, strict: bool) -> Result<(), WalletError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)?.permissions().mode() & 0o777;
+    if mode & 0o077 == 0 {
+        return Ok(());
+    }
+    let err = WalletError::InsecureKeyFile {
+        path: path.to_string(),
+        mode,
+    };
+    if strict {
+        return Err(err);
+    }
+    log::warn!("{}", err);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &str, _strict: bool) -> Result<(), WalletError> {
+    Ok(())
+}
+
+fn keypair_from_stored_seed(
+    seed: Vec<u8>,
+    pubkey: String,
+    key_error: &dyn Fn(String) -> WalletError,
+) -> Result<Keypair, WalletError> {
+    let seed: [u8; 32] = seed.try_into()
+        .map_err(|key: Vec<u8>| key_error(format!("expected a 32-byte private key, found {} bytes", key.len())))?;
+    let keypair = Keypair::from_seed(&seed).map_err(|e| key_error(e.to_string()))?;
+
+    // Guard against hand-edited or misassembled files before we sign anything.
+    if keypair.pubkey().to_string() != pubkey {
+        return Err(WalletError::KeyMismatch {
+            expected: pubkey,
+            actual: keypair.pubkey(),
+        });
+    }
+    Ok(keypair)
+}
+
+/// Rewrites a plaintext keyfile (either format) as an `EncryptedKeyFile`.
+pub fn encrypt_keyfile(input: &str, output: &str, passphrase_env: Option<&str>) -> Result<(), WalletError> {
+    let content = fs::read_to_string(input)?;
+    if serde_json::from_str::<EncryptedKeyFile>(&content).is_ok() {
+        return Err(WalletError::KeyFileParse {
+            path: input.to_string(),
+            reason: "keyfile is already encrypted".to_string(),
+        });
+    }
+    let keypair = load_keypair_from_file(input, &KeyLoadOptions::default())?;
+
+    let passphrase = read_passphrase(passphrase_env, "New passphrase: ", true)?;
+    if passphrase.is_empty() {
+        return Err(WalletError::Config("passphrase must not be empty".to_string()));
+    }
+
+    let encrypted = encrypt_seed(&keypair, &passphrase).map_err(|reason| WalletError::KeyFileParse {
+        path: input.to_string(),
+        reason,
+    })?;
+    let json = serde_json::to_string_pretty(&encrypted)
+        .map_err(|e| WalletError::Config(e.to_string()))?;
+
+    // Write next to the target and rename so a failure never leaves a half-written keyfile.
+    let tmp = format!("{}.tmp", output);
+    write_private_file(&tmp, &json, true)?;
+    fs::rename(&tmp, output)?;
+    log::info!("Encrypted keyfile for {} written to {}", keypair.pubkey(), output);
+    Ok(())
+}
+
+fn read_passphrase(env: Option<&str>, prompt: &str, confirm: bool) -> Result<String, WalletError> {
+    if let Some(var) = env {
+        return std::env::var(var)
+            .map_err(|_| WalletError::Config(format!("passphrase environment variable {} is not set", var)));
+    }
+
+    let passphrase = rpassword::prompt_password(prompt)?;
+    if confirm && rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+        return Err(WalletError::Config("passphrases do not match".to_string()));
+    }
+    Ok(passphrase)
+}
+
+fn derive_key(passphrase: &str, kdf: &ScryptParams) -> Result<[u8; 32], String> {
+    let params = scrypt::Params::new(kdf.log_n, kdf.r, kdf.p, 32).map_err(|e| e.to_string())?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), &kdf.salt, &params, &mut key).map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn encrypt_seed(keypair: &Keypair, passphrase: &str) -> Result<EncryptedKeyFile, String> {
+    let mut salt = vec![0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let kdf = ScryptParams {
+        log_n: SCRYPT_LOG_N,
+        r: SCRYPT_R,
+        p: SCRYPT_P,
+        salt,
+    };
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &kdf)?).map_err(|e| e.to_string())?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, keypair.secret().as_bytes().as_ref())
+        .map_err(|e| e.to_string())?;
+
+    Ok(EncryptedKeyFile {
+        pubkey: keypair.pubkey().to_string(),
+        kdf,
+        nonce: nonce.to_vec(),
+        ciphertext,
+    })
+}
+
+fn decrypt_seed(encrypted: &EncryptedKeyFile, passphrase: &str) -> Result<Vec<u8>, String> {
+    if encrypted.nonce.len() != 12 {
+        return Err(format!("invalid nonce length {}", encrypted.nonce.len()));
+    }
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &encrypted.kdf)?).map_err(|e| e.to_string())?;
+    cipher
+        .decrypt(Nonce::from_slice(&encrypted.nonce), encrypted.ciphertext.as_ref())
+        .map_err(|_| "wrong passphrase or corrupted keyfile".to_string())
+}
+
+/// Reads a base58-encoded 64-byte secret key (as printed by most Solana
+/// wallets) from an environment variable, so keys never touch the disk.
+pub fn load_keypair_from_env(var: &str) -> Result<Keypair, WalletError> {
+    let env_error = |reason: String| WalletError::KeyEnv {
+        var: var.to_string(),
+        reason,
+    };
+    let encoded = std::env::var(var).map_err(|e| env_error(e.to_string()))?;
+    let bytes = bs58::decode(encoded.trim())
+        .into_vec()
+        .map_err(|e| env_error(format!("invalid base58: {}", e)))?;
+    if bytes.len() != 64 {
+        return Err(env_error(format!("expected a 64-byte secret key, found {} bytes", bytes.len())));
+    }
+    Keypair::from_bytes(&bytes).map_err(|e| env_error(e.to_string()))
+}
+
+/// Opens the first connected Ledger as a signer for the key at `path`
+/// (e.g. `m/44'/501'/0'/0'`). Every signature needs confirming on the device.
+#[cfg(feature = "ledger")]
+pub fn load_ledger_signer(path: &str) -> Result<Box<dyn Signer>, WalletError> {
+    use solana_remote_wallet::{
+        locator::Locator, remote_keypair::generate_remote_keypair, remote_wallet::maybe_wallet_manager,
+    };
+
+    let ledger_error = |reason: String| WalletError::RemoteSigner(reason);
+    let derivation_path = DerivationPath::from_absolute_path_str(path)
+        .map_err(|e| ledger_error(format!("invalid derivation path {}: {}", path, e)))?;
+    let wallet_manager = maybe_wallet_manager()
+        .map_err(|e| ledger_error(e.to_string()))?
+        .ok_or_else(|| ledger_error("no hardware wallet found".to_string()))?;
+    let locator = Locator::new_from_path("usb://ledger").map_err(|e| ledger_error(e.to_string()))?;
+
+    let signer = generate_remote_keypair(locator, derivation_path, &wallet_manager, true, "owner")
+        .map_err(|e| ledger_error(e.to_string()))?;
+    log::info!("Using Ledger key {} at {}", signer.pubkey(), path);
+    Ok(Box::new(signer))
+}
+
+/// Derives the owner keypair from a BIP39 mnemonic, by default on the
+/// standard Solana derivation path m/44'/501'/0'/0'.
+pub fn load_keypair_from_mnemonic(phrase: &str, passphrase: &str, path: Option<&str>) -> Result<Keypair, WalletError> {
+    let mnemonic = Mnemonic::parse_normalized(phrase.trim())
+        .map_err(|e| WalletError::Mnemonic(format!("failed BIP39 validation: {}", e)))?;
+    let seed = mnemonic.to_seed(passphrase);
+    let keypair = match path {
+        Some(RAW_SEED_PATH) => Keypair::from_seed(&seed[..32]),
+        Some(path) => Keypair::from_seed_and_derivation_path(&seed, Some(parse_derivation_path(path)?)),
+        None => Keypair::from_seed_and_derivation_path(&seed, Some(DerivationPath::new_bip44(Some(0), Some(0)))),
+    }
+    .map_err(|e| WalletError::Mnemonic(e.to_string()))?;
+    log::info!("Derived {} from the mnemonic at {}", keypair.pubkey(), path.unwrap_or(DEFAULT_DERIVATION_PATH));
+    Ok(keypair)
+}
+
+/// Parses a BIP44 path under the Solana coin type, either in full
+/// (m/44'/501'/1'/0') or as the account-only shorthand (m/44'/501'/1').
+/// Ed25519 derivation only supports hardened indices, so every index is
+/// treated as hardened whether or not it is marked with `'`.
+fn parse_derivation_path(path: &str) -> Result<DerivationPath, WalletError> {
+    let invalid = || WalletError::Mnemonic(format!(
+        "invalid derivation path {}: expected m/44'/501'/<account>' or m/44'/501'/<account>'/<change>'",
+        path
+    ));
+    let indices = path
+        .trim()
+        .strip_prefix("m/")
+        .ok_or_else(invalid)?
+        .split('/')
+        .map(|index| index.trim_end_matches('\'').parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<u32>, WalletError>>()?;
+
+    match indices[..] {
+        [44, 501, account] => Ok(DerivationPath::new_bip44(Some(account), None)),
+        [44, 501, account, change] => Ok(DerivationPath::new_bip44(Some(account), Some(change))),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE_PUBKEY: &str = "9C6hybhQ6Aycep9jaUnP6uL9ZYvDjUp1aSkFWPUFJtpj";
+
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn load(name: &str) -> Result<Keypair, WalletError> {
+        load_keypair_from_file(&fixture(name), &KeyLoadOptions::default())
+    }
+
+    #[test]
+    fn loads_cli_keypair_array() {
+        assert_eq!(load("cli_keypair.json").unwrap().pubkey().to_string(), FIXTURE_PUBKEY);
+    }
+
+    #[test]
+    fn loads_wallet_keyfile() {
+        assert_eq!(load("wallet_keyfile.json").unwrap().pubkey().to_string(), FIXTURE_PUBKEY);
+    }
+
+    #[test]
+    fn rejects_cli_array_of_the_wrong_length() {
+        let err = load("cli_keypair_short.json").unwrap_err();
+        assert!(
+            matches!(&err, WalletError::KeyFileParse { reason, .. } if reason.contains("found 32 bytes")),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn rejects_wallet_seed_of_the_wrong_length() {
+        let err = load("wallet_keyfile_short.json").unwrap_err();
+        assert!(
+            matches!(&err, WalletError::KeyFileParse { reason, .. } if reason.contains("found 31 bytes")),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn rejects_wallet_keyfile_with_another_pubkey() {
+        let err = load("wallet_keyfile_mismatch.json").unwrap_err();
+        match err {
+            WalletError::KeyMismatch { expected, actual } => {
+                assert_eq!(expected, "J2xccRtuG43drESLYznHhLhQkLTdfepcKYbiQ9BsJVaf");
+                assert_eq!(actual.to_string(), FIXTURE_PUBKEY);
+            }
+            err => panic!("expected KeyMismatch, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn missing_keyfile_names_the_path() {
+        let path = fixture("missing.json");
+        let err = load_keypair_from_file(&path, &KeyLoadOptions::default()).unwrap_err();
+        assert!(matches!(&err, WalletError::KeyFileParse { path: failed, .. } if *failed == path), "{:?}", err);
+    }
+
+    #[test]
+    fn rejects_input_in_neither_format() {
+        let err = load_keypair_from_reader("not a key".as_bytes(), "input", &KeyLoadOptions::default()).unwrap_err();
+        assert!(matches!(err, WalletError::KeyFileParse { .. }), "{:?}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn overwriting_a_keyfile_restricts_its_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("vm_wallet_keyfile_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "old").unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private_file(path, "new", true).unwrap();
+        let mode = fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let contents = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(mode, 0o600);
+        assert_eq!(contents, "new");
+    }
+
+    #[test]
+    fn serialized_keyfile_format_loads_back() {
+        let keypair = Keypair::new();
+        let stored = KeyFileFormat::new(&keypair);
+        assert_eq!(stored.pubkey(), keypair.pubkey().to_string());
+        assert_eq!(stored.keypair().unwrap().pubkey(), keypair.pubkey());
+
+        let contents = serde_json::to_string(&stored).unwrap();
+        let loaded = load_keypair_from_reader(contents.as_bytes(), "input", &KeyLoadOptions::default()).unwrap();
+        assert_eq!(loaded.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn formatted_keyfile_loads_back() {
+        let keypair = Keypair::new();
+        let contents = format_keyfile(&keypair);
+        let loaded = load_keypair_from_reader(contents.as_bytes(), "input", &KeyLoadOptions::default()).unwrap();
+        assert_eq!(loaded.pubkey(), keypair.pubkey());
+    }
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn mnemonic_derivations_are_pinned() {
+        let bip44 = load_keypair_from_mnemonic(PHRASE, "", None).unwrap();
+        assert_eq!(bip44.pubkey().to_string(), "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+        let raw = load_keypair_from_mnemonic(PHRASE, "", Some(RAW_SEED_PATH)).unwrap();
+        assert_eq!(raw.pubkey().to_string(), "EHqmfkN89RJ7Y33CXM6uCzhVeuywHoJXZZLszBHHZy7o");
+    }
+
+    #[test]
+    fn prompt_keyfile_matches_raw_seed_derivation() {
+        let path = std::env::temp_dir().join(format!("vm_wallet_mnemonic_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        save_mnemonic_keypair(path, PHRASE).unwrap();
+        let saved = load_keypair_from_file(path, &KeyLoadOptions::default());
+        fs::remove_file(path).unwrap();
+        assert_eq!(saved.unwrap().pubkey().to_string(), "EHqmfkN89RJ7Y33CXM6uCzhVeuywHoJXZZLszBHHZy7o");
+    }
+}
@@ -0,0 +1,2052 @@
+//! Timelock unlocks for Code VM accounts. The `vm_wallet` binary is a thin
+//! wrapper around `run`; embedders can drive an `UnlockContext` directly.
+
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::Signer,
+    signature::{Keypair, Signature},
+    transaction::{TransactionError, VersionedTransaction},
+    message::{v0, Message, VersionedMessage},
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    hash::Hash,
+    system_instruction,
+    sysvar::{self, clock::Clock, Sysvar},
+    account_info::AccountInfo,
+    account::Account,
+    instruction::Instruction,
+    compute_budget::ComputeBudgetInstruction,
+    commitment_config::CommitmentConfig,
+    packet::PACKET_DATA_SIZE,
+};
+
+use code_vm_api::prelude::*;
+use solana_client::{nonce_utils, rpc_config::RpcSendTransactionConfig};
+use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionTokenBalance};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use tokio::sync::OnceCell;
+use futures::StreamExt;
+use log::{debug, info, warn};
+
+pub mod audit;
+pub mod cli;
+pub mod config;
+pub mod doctor;
+pub mod error;
+pub mod keys;
+pub mod networks;
+pub mod output;
+pub mod pdas;
+pub mod processed;
+pub mod rpc;
+pub mod sponsor;
+pub mod txfile;
+
+use cli::{parse_commitment, parse_pubkey, Args, Command, TxEncoding, UnlockStep, STDIN_PATH};
+use config::WalletConfig;
+use output::{BatchReport, Confirmation, OwnerResult, Report, StatusReport, TokenBalance, TransactionResult, TxRecord};
+use rpc::{RetryPolicy, RpcPool, VmRpc};
+pub use error::WalletError;
+pub use keys::KeyFileFormat;
+pub use pdas::{
+    derive_associated_token_account, derive_timelock, derive_unlock, derive_unlock_pdas, TimelockParams, UnlockParams,
+    UnlockPdas,
+};
+
+use keys::{
+    encrypt_keyfile, generate_keyfile, load_keypair_from_env, load_keypair_from_file, load_keypair_from_mnemonic,
+    save_mnemonic_keypair, KeyLoadOptions,
+};
+
+/// Default mainnet RPC endpoint
+pub const RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+
+/// Code VM program and the mainnet VM this wallet unlocks from
+pub const VM_PROGRAM_ID: &str = "vmZ1WUq8SxjBWcaeTCvgJRZbS84R61uniFsQy5YMRTJ";
+pub const MINT_ADDRESS: &str = "kinXdEcpDQeHPEuQnqmUgtYykqKGVFq6CeVX5iAHJq6";
+pub const VM_STATE_ACCOUNT: &str = "FDrssd3RVeCkgHAT2NkEpkxC5UgfJpKHeebXUMnuzD6D";
+pub const VM_AUTHORITY: &str = "f1ipC31qd2u88MjNYp1T4Cc7rnWfM9ivYpTV1Z8FHnD";
+
+/// Lock duration in days of the mainnet VM's timelocks
+pub const LOCK_DURATION: u8 = 21;
+
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Performance samples (one per minute) averaged for slot time estimates.
+const SLOT_TIME_SAMPLES: usize = 30;
+
+/// Signatures broadcast but not yet confirmed or failed, reported if the run
+/// is interrupted so it is never unclear whether a transaction was sent.
+static IN_FLIGHT: Mutex<Vec<Signature>> = Mutex::new(Vec::new());
+
+// PDA seeds
+const CODE_VM: &[u8] = b"code_vm";
+const VM_UNLOCK_ACCOUNT: &[u8] = b"vm_unlock_pda_account";
+
+/// One owner's timelock unlock against a Code VM: the RPC client, keys and
+/// sending options every step uses. Build one with `new`, or from parsed
+/// command line arguments with `build_context`.
+pub struct UnlockContext {
+    rpc: Box<dyn VmRpc>,
+    program_id: Pubkey,
+    vm_state: Pubkey,
+    mint: Pubkey,
+    vm_authority: Pubkey,
+    lock_duration: u8,
+    /// Commitment accounts are read at
+    read_commitment: CommitmentConfig,
+    /// Local keypair or, with the `ledger` feature, a hardware wallet
+    owner: Box<dyn Signer>,
+    /// Separate fee payer; `None` when the owner pays its own fees
+    payer: Option<Keypair>,
+    send: SendOptions,
+    wait: WaitOptions,
+    vm_account: OnceCell<CodeVmAccount>,
+    /// Accounts read by `preflight`, each served once
+    prefetched: Mutex<HashMap<Pubkey, Option<Account>>>,
+    /// Slot each account was last read at, for the `--max-age-slots` guard
+    read_slots: Mutex<HashMap<Pubkey, u64>>,
+    transactions: Mutex<Vec<TxRecord>>,
+    /// When the context was set up, for the run duration in the summary
+    started: Instant,
+}
+
+/// Options controlling how transactions are submitted.
+#[derive(Debug, Clone)]
+pub struct SendOptions {
+    /// Simulate transactions instead of sending them
+    pub dry_run: bool,
+    /// Simulate each transaction and only send it if the simulation passes
+    pub simulate_first: bool,
+    /// Compute unit price in micro-lamports; 0 adds no priority fee
+    pub priority_fee: u64,
+    /// Compute unit limit to request, if any
+    pub compute_limit: Option<u32>,
+    /// Preflight settings used when broadcasting
+    pub send_config: RpcSendTransactionConfig,
+    /// Commitment a sent transaction must reach
+    pub confirm_commitment: CommitmentConfig,
+    /// How long to wait for confirmation after sending
+    pub confirm_timeout: Duration,
+    /// How many times to re-sign and resend after the blockhash expires
+    pub max_resends: u32,
+    /// Largest fee in lamports a single transaction may cost
+    pub max_fee_lamports: u64,
+    /// Address lookup table for v0 transactions; legacy transactions without one
+    pub lookup_table: Option<Pubkey>,
+    /// Durable nonce to sign against instead of a recent blockhash
+    pub nonce: Option<NonceConfig>,
+    /// Blockhash to sign against instead of fetching the latest one
+    pub blockhash: Option<Hash>,
+    /// Re-check the unlock state before finalizing, allowing reads this many slots old
+    pub max_age_slots: Option<u64>,
+    /// Print every instruction in full before signing
+    pub show_instruction: bool,
+    /// Check the fee payer can cover fees and rent before sending
+    pub check_balance: bool,
+    /// Memo recorded on-chain with each transaction, signed by the owner
+    pub memo: Option<String>,
+    /// Remote service that pays fees and co-signs as fee payer
+    pub sponsor: Option<SponsorConfig>,
+    /// Log of confirmed steps to consult and append to
+    pub processed_log: Option<String>,
+    /// File every step attempt and its outcome is appended to
+    pub audit_log: Option<String>,
+}
+
+/// The binary's defaults: confirmed commitment, a 60s confirmation timeout,
+/// three resends and a 0.01 SOL fee cap.
+impl Default for SendOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            simulate_first: false,
+            priority_fee: 0,
+            compute_limit: None,
+            send_config: RpcSendTransactionConfig::default(),
+            confirm_commitment: CommitmentConfig::confirmed(),
+            confirm_timeout: Duration::from_secs(60),
+            max_resends: 3,
+            max_fee_lamports: 10_000_000,
+            lookup_table: None,
+            nonce: None,
+            blockhash: None,
+            max_age_slots: None,
+            show_instruction: false,
+            check_balance: false,
+            memo: None,
+            sponsor: None,
+            processed_log: None,
+            audit_log: None,
+        }
+    }
+}
+
+/// A fee sponsor: the service URL and the pubkey it signs as fee payer with.
+#[derive(Debug, Clone)]
+pub struct SponsorConfig {
+    pub url: String,
+    pub pubkey: Pubkey,
+}
+
+/// How long and how often to poll while waiting out the timelock.
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+    pub interval: Duration,
+    pub timeout: Option<Duration>,
+}
+
+/// Polls once a minute with no timeout, like the binary.
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            timeout: None,
+        }
+    }
+}
+
+/// A durable nonce account and the authority allowed to advance it.
+#[derive(Debug, Clone)]
+pub struct NonceConfig {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+}
+
+/// Sends the transaction and polls its status until it reaches `commitment`,
+/// its blockhash expires, or `confirm_timeout` elapses. `on_sent` runs once
+/// the transaction is broadcast, before any polling.
+#[allow(clippy::too_many_arguments)]
+async fn send_and_confirm(
+    rpc: &dyn VmRpc,
+    tx: &VersionedTransaction,
+    send_config: RpcSendTransactionConfig,
+    label: &str,
+    last_valid_block_height: u64,
+    commitment: CommitmentConfig,
+    confirm_timeout: Duration,
+    on_sent: impl FnOnce(&Signature),
+) -> Result<Signature, WalletError> {
+    // Resending the same signed transaction is safe: it can land at most once.
+    let signature = match rpc.send_transaction(tx, send_config).await {
+        Ok(signature) => signature,
+        Err(err) if err.get_transaction_error() == Some(TransactionError::BlockhashNotFound) => {
+            return Err(WalletError::BlockhashExpired { signature: tx.signatures[0] });
+        }
+        Err(err) => return Err(err.into()),
+    };
+    // Shown at the default level, so a confirmation that times out still
+    // leaves something to look up.
+    info!("{} transaction sent: {}", label, output::explorer_url(&signature.to_string()));
+
+    IN_FLIGHT.lock().unwrap().push(signature);
+    on_sent(&signature);
+    let confirmed = poll_confirmation(rpc, signature, last_valid_block_height, commitment, confirm_timeout).await;
+    IN_FLIGHT.lock().unwrap().retain(|sent| *sent != signature);
+    confirmed
+}
+
+async fn poll_confirmation(
+    rpc: &dyn VmRpc,
+    signature: Signature,
+    last_valid_block_height: u64,
+    commitment: CommitmentConfig,
+    confirm_timeout: Duration,
+) -> Result<Signature, WalletError> {
+    let deadline = Instant::now() + confirm_timeout;
+    loop {
+        match rpc.get_signature_status(&signature).await? {
+            Some(status) => {
+                if let Some(err) = status.err {
+                    return Err(WalletError::TransactionFailed {
+                        signature,
+                        reason: err.to_string(),
+                    });
+                }
+                if status.satisfies_commitment(commitment) {
+                    return Ok(signature);
+                }
+            }
+            None => {
+                let block_height = rpc.get_block_height().await?;
+                if block_height > last_valid_block_height {
+                    return Err(WalletError::BlockhashExpired { signature });
+                }
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(WalletError::ConfirmationTimeout { signature });
+        }
+        tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+    }
+}
+
+/// Fetches a confirmed transaction once and parses its metadata for everyone
+/// who reports on it. This is informational only, so failures are logged and
+/// reported as unknown.
+async fn fetch_tx_result(rpc: &dyn VmRpc, signature: &Signature) -> TransactionResult {
+    match rpc.get_transaction(signature).await {
+        Ok(tx) => match tx.transaction.meta {
+            Some(meta) => TransactionResult {
+                fee_lamports: Some(meta.fee),
+                compute_units: meta.compute_units_consumed.into(),
+                pre_token_balances: token_balances(meta.pre_token_balances.into()),
+                post_token_balances: token_balances(meta.post_token_balances.into()),
+            },
+            None => TransactionResult::default(),
+        },
+        Err(err) => {
+            debug!("Could not fetch transaction {} for its result: {}", signature, err);
+            TransactionResult::default()
+        }
+    }
+}
+
+fn token_balances(balances: Option<Vec<UiTransactionTokenBalance>>) -> Vec<TokenBalance> {
+    balances
+        .unwrap_or_default()
+        .into_iter()
+        .map(|balance| TokenBalance {
+            account_index: balance.account_index,
+            mint: balance.mint,
+            owner: balance.owner.into(),
+            amount: balance.ui_token_amount.amount,
+            decimals: balance.ui_token_amount.decimals,
+        })
+        .collect()
+}
+
+/// Checks a confirmed signature once more to report how final it is. This is
+/// informational only, so failures are logged and reported as unknown.
+async fn fetch_confirmation(rpc: &dyn VmRpc, signature: &Signature) -> Confirmation {
+    match rpc.get_signature_status(signature).await {
+        Ok(Some(status)) => Confirmation {
+            confirmation_status: Some(match status.confirmation_status() {
+                TransactionConfirmationStatus::Processed => "processed",
+                TransactionConfirmationStatus::Confirmed => "confirmed",
+                TransactionConfirmationStatus::Finalized => "finalized",
+            }),
+            confirmations: status.confirmations,
+        },
+        Ok(None) => Confirmation::default(),
+        Err(err) => {
+            debug!("Could not fetch the status of {}: {}", signature, err);
+            Confirmation::default()
+        }
+    }
+}
+
+fn log_confirmation(label: &str, confirmation: &Confirmation) {
+    match (confirmation.confirmation_status, confirmation.confirmations) {
+        (Some(status), Some(count)) => info!("{} is {} with {} confirmations", label, status, count),
+        (Some(status), None) => info!("{} is {}", label, status),
+        (None, _) => {}
+    }
+}
+
+/// Logs a confirmed transaction's fee and compute units.
+fn log_tx_result(label: &str, result: &TransactionResult) {
+    if let Some(fee) = result.fee_lamports {
+        info!("{} paid {} lamports in fees", label, fee);
+    }
+    if let Some(units) = result.compute_units {
+        info!("{} used {} compute units", label, units);
+    }
+}
+
+/// VM accounts start with their `AccountType` discriminator. Memory, storage
+/// and other VM accounts can be large enough to pass the length check, so
+/// this runs before any `unpack` casts the data to the expected type.
+fn check_account_type(address: &Pubkey, data: &[u8], expected: AccountType, name: &str) -> Result<(), WalletError> {
+    let expected = expected as u8;
+    match data.first() {
+        Some(&found) if found == expected => Ok(()),
+        found => Err(WalletError::InvalidAccountData {
+            account: *address,
+            reason: format!(
+                "account type {} is not a {} account (type {})",
+                found.map_or("missing".to_string(), |found| found.to_string()),
+                name,
+                expected
+            ),
+        }),
+    }
+}
+
+/// Signs `message` with the signers we hold and leaves the remaining
+/// required signatures blank for someone else to fill in.
+fn partially_sign(message: VersionedMessage, signers: &[&dyn Signer]) -> Result<VersionedTransaction, WalletError> {
+    let required = message.header().num_required_signatures as usize;
+    let data = message.serialize();
+    let mut signatures = vec![Signature::default(); required];
+    for signer in signers {
+        let index = message.static_account_keys()[..required]
+            .iter()
+            .position(|key| *key == signer.pubkey())
+            .ok_or_else(|| WalletError::TransactionBuild(format!("{} is not a required signer", signer.pubkey())))?;
+        signatures[index] = signer
+            .try_sign_message(&data)
+            .map_err(|e| WalletError::TransactionBuild(e.to_string()))?;
+    }
+    Ok(VersionedTransaction { signatures, message })
+}
+
+fn rpc_pool(config: &WalletConfig) -> Result<RpcPool, WalletError> {
+    Ok(RpcPool::new(
+        config.rpc_urls(),
+        config.commitment_config()?,
+        config.rpc_timeout.map(Duration::from_secs),
+        RetryPolicy {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_millis(config.retry_delay_ms),
+        },
+    ))
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    let naive = NaiveDateTime::from_timestamp_opt(timestamp, 0)
+        .unwrap_or_default();
+    let datetime: DateTime<Utc> = DateTime::from_naive_utc_and_offset(naive, Utc);
+    datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+/// Formats a number of seconds as e.g. "3d 4h 12m".
+fn format_remaining(seconds: i64) -> String {
+    if seconds <= 0 {
+        return "0m (ready to finalize)".to_string();
+    }
+    let days = seconds / 86_400;
+    let hours = (seconds % 86_400) / 3_600;
+    let minutes = (seconds % 3_600) / 60;
+    format!("{}d {}h {}m", days, hours, minutes)
+}
+
+impl UnlockContext {
+    /// Sets up an unlock for `owner` against the VM and endpoints in
+    /// `config`. `payer` pays fees and rent in the owner's place when set.
+    pub fn new(
+        config: &WalletConfig,
+        owner: Box<dyn Signer>,
+        payer: Option<Keypair>,
+        send: SendOptions,
+        wait: WaitOptions,
+    ) -> Result<Self, WalletError> {
+        Self::with_rpc(Box::new(rpc_pool(config)?), config, owner, payer, send, wait)
+    }
+
+    /// Like `new`, but talks to the cluster through `rpc`.
+    fn with_rpc(
+        rpc: Box<dyn VmRpc>,
+        config: &WalletConfig,
+        owner: Box<dyn Signer>,
+        payer: Option<Keypair>,
+        send: SendOptions,
+        wait: WaitOptions,
+    ) -> Result<Self, WalletError> {
+        if let Some(nonce) = &send.nonce {
+            let fee_payer = match (&send.sponsor, &payer) {
+                (Some(sponsor), _) => sponsor.pubkey,
+                (None, payer) => payer.as_ref().map_or(owner.pubkey(), Signer::pubkey),
+            };
+            if nonce.authority != owner.pubkey() && nonce.authority != fee_payer {
+                return Err(WalletError::Config(
+                    "--nonce-authority must be the owner or the fee payer".to_string(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            rpc,
+            program_id: Pubkey::from_str(VM_PROGRAM_ID).expect("valid VM program id"),
+            vm_state: parse_pubkey("vm-state", &config.vm_state)?,
+            mint: parse_pubkey("mint", &config.mint)?,
+            vm_authority: parse_pubkey("vm-authority", &config.vm_authority)?,
+            lock_duration: config.lock_duration,
+            read_commitment: config.commitment_config()?,
+            owner,
+            payer,
+            send,
+            wait,
+            vm_account: OnceCell::new(),
+            prefetched: Mutex::new(HashMap::new()),
+            read_slots: Mutex::new(HashMap::new()),
+            transactions: Mutex::new(Vec::new()),
+            started: Instant::now(),
+        })
+    }
+
+    fn timelock_params(&self) -> TimelockParams {
+        TimelockParams {
+            mint: self.mint,
+            vm_authority: self.vm_authority,
+            owner: self.owner.pubkey(),
+            lock_duration: self.lock_duration,
+        }
+    }
+
+    /// The owner's unlock PDA for this VM and its bump.
+    pub fn get_unlock_pda(&self) -> (Pubkey, u8) {
+        let pdas = derive_unlock_pdas(&self.timelock_params(), &self.vm_state);
+        (pdas.unlock_pda, pdas.unlock_bump)
+    }
+
+    /// Whether the unlock account exists. An account that does is kept for
+    /// the state read that follows, so checking costs no extra round trip.
+    /// A missing one is not: the next read usually follows the unlock send.
+    async fn check_unlock_account(&self, unlock_pda: &Pubkey) -> Result<bool, WalletError> {
+        let account = self.fetch_account(unlock_pda).await?;
+        let exists = account.is_some();
+        if exists {
+            self.prefetched.lock().unwrap().insert(*unlock_pda, account);
+        }
+        Ok(exists)
+    }
+
+    /// Reads the VM state, unlock account and clock in a single round trip
+    /// so the first checks of a run need no further RPC calls.
+    pub async fn preflight(&self, unlock_pda: &Pubkey) -> Result<(), WalletError> {
+        let addresses = [self.vm_state, *unlock_pda, sysvar::clock::id()];
+        let (slot, accounts) = self.rpc.get_multiple_accounts(&addresses).await?;
+        self.read_slots.lock().unwrap().extend(addresses.into_iter().map(|address| (address, slot)));
+        self.prefetched.lock().unwrap().extend(addresses.into_iter().zip(accounts));
+        self.check_vm_state().await
+    }
+
+    /// Fetches an account, using a result from `preflight` the first time the
+    /// address is read. Later reads always go to the cluster.
+    async fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, WalletError> {
+        let prefetched = self.prefetched.lock().unwrap().remove(address);
+        match prefetched {
+            Some(account) => Ok(account),
+            None => {
+                let (slot, account) = self.rpc.get_account_with_slot(address, self.read_commitment).await?;
+                self.read_slots.lock().unwrap().insert(*address, slot);
+                Ok(account)
+            }
+        }
+    }    
+    
+    fn create_unlock_ix(&self, unlock_pda: &Pubkey) -> solana_sdk::instruction::Instruction {
+        timelock_unlock_init(
+            self.owner.pubkey(),
+            self.fee_payer(),
+            self.vm_state,  // Using renamed field
+            *unlock_pda
+        )
+    }
+
+    fn verify_unlock_pda(&self, unlock_pda: &Pubkey) -> Result<bool, WalletError> {
+        let owner_pubkey = self.owner.pubkey();
+        let (timelock_address, _) = derive_timelock(&self.timelock_params());
+        
+        let seeds = &[
+            CODE_VM,
+            VM_UNLOCK_ACCOUNT,
+            owner_pubkey.as_ref(),
+            timelock_address.as_ref(),
+            self.vm_state.as_ref()
+        ];
+    
+        let (expected_pda, bump) = Pubkey::find_program_address(seeds, &self.program_id);
+        debug!("Timelock address: {}", timelock_address);
+        debug!("Expected unlock PDA: {} (bump {})", expected_pda, bump);
+        
+        Ok(*unlock_pda == expected_pda)
+    }    
+
+    async fn send_unlock_tx(&self) -> Result<(), WalletError> {
+        let (unlock_pda, _) = self.get_unlock_pda();
+        let ix = self.create_unlock_ix(&unlock_pda);
+        debug!("Derived Unlock PDA: {}", unlock_pda);
+        
+        // timelock_unlock_init creates the unlock account, funded by the fee payer.
+        let created = Some(UnlockStateAccount::get_size());
+        self.process_step(&unlock_pda, &[ix], created, "unlock", None, "Unlock").await
+    }
+
+    /// Starts the timelock unlock without waiting for it, creating the unlock
+    /// PDA if needed, and reports when the lock window expires.
+    pub async fn initiate_unlock(&self, unlock_pda: &Pubkey) -> Result<(), WalletError> {
+        if self.check_unlock_account(unlock_pda).await? {
+            info!("Unlock already initiated");
+        } else {
+            info!("Initializing new unlock...");
+            self.send_unlock_tx().await?;
+            if self.send.dry_run {
+                return self.report_estimated_unlock_at().await;
+            }
+        }
+
+        // The unlock account may not be visible at the read commitment yet
+        // right after sending; fall back to the estimate until it is.
+        let unlock_state = match self.get_unlock_state(unlock_pda).await {
+            Ok(unlock_state) => unlock_state,
+            Err(WalletError::AccountNotFound(_)) => return self.report_estimated_unlock_at().await,
+            Err(err) => return Err(err),
+        };
+        if unlock_state.is_unlocked() {
+            info!("Account is already unlocked!");
+        } else {
+            let current_time = self.get_chain_time().await?;
+            info!(
+                "Timelock expires at: {} ({}), as recorded on-chain",
+                unlock_state.unlock_at, format_timestamp(unlock_state.unlock_at)
+            );
+            info!("Account unlocks in {}", format_remaining(unlock_state.unlock_at - current_time));
+        }
+        Ok(())
+    }
+
+    /// Logs when an unlock started now would expire: the cluster clock plus
+    /// the lock duration in days. The on-chain `unlock_at` is authoritative
+    /// and is reported instead whenever the unlock account can be read.
+    async fn report_estimated_unlock_at(&self) -> Result<(), WalletError> {
+        let current_time = self.get_chain_time().await?;
+        let unlock_at = current_time + i64::from(self.lock_duration) * 86_400;
+        info!(
+            "Estimated timelock expiry: {} ({}), {} days from now",
+            unlock_at, format_timestamp(unlock_at), self.lock_duration
+        );
+        Ok(())
+    }
+
+    /// Finalizes an unlock whose timelock has passed, without waiting. While
+    /// it is still running this returns `NotUnlocked`.
+    pub async fn finalize_unlock(&self, unlock_pda: &Pubkey) -> Result<(), WalletError> {
+        let unlock_state = self.get_unlock_state(unlock_pda).await?;
+        if unlock_state.is_unlocked() {
+            info!("Account is already unlocked!");
+            return Ok(());
+        }
+        if !unlock_state.is_waiting() {
+            return Err(WalletError::InvalidUnlockState);
+        }
+
+        let current_time = self.get_chain_time().await?;
+        if current_time < unlock_state.unlock_at {
+            info!("Timelock still active, account unlocks in {}", format_remaining(unlock_state.unlock_at - current_time));
+            return Err(WalletError::NotUnlocked { unlock_at: unlock_state.unlock_at });
+        }
+        self.send_finalize_unlock_tx(unlock_pda, unlock_state.unlock_at).await
+    }
+
+    fn create_finalize_unlock_ix(&self, unlock_pda: &Pubkey) -> solana_sdk::instruction::Instruction {
+        timelock_unlock_finalize(
+            self.owner.pubkey(),
+            self.fee_payer(),
+            self.vm_state,
+            *unlock_pda
+        )
+    }
+
+    async fn get_unlock_state(&self, unlock_pda: &Pubkey) -> Result<UnlockStateAccount, WalletError> {
+        let account = self.get_existing_account(unlock_pda).await?;
+        self.check_layout(unlock_pda, &account, AccountType::UnlockStateAccount, "unlock state", UnlockStateAccount::get_size())?;
+        Ok(UnlockStateAccount::unpack(&account.data))
+    }
+
+    /// Confirms `--vm-state` names a VM account before anything is signed
+    /// against it.
+    /// Also checks the configured lock duration against the VM's, since a
+    /// mismatch silently derives the wrong timelock and unlock PDAs.
+    async fn check_vm_state(&self) -> Result<(), WalletError> {
+        let vm = self.vm_account().await?;
+        if vm.lock_duration != self.lock_duration {
+            return Err(WalletError::LockDurationMismatch {
+                configured: self.lock_duration,
+                actual: vm.lock_duration,
+            });
+        }
+        Ok(())
+    }
+
+    /// The unpacked VM state account, fetched on first use and reused after.
+    async fn vm_account(&self) -> Result<&CodeVmAccount, WalletError> {
+        self.vm_account
+            .get_or_try_init(|| async {
+                let account = self.get_existing_account(&self.vm_state).await?;
+                self.check_layout(&self.vm_state, &account, AccountType::CodeVmAccount, "VM state", CodeVmAccount::get_size())?;
+                Ok(CodeVmAccount::unpack(&account.data))
+            })
+            .await
+    }
+
+    /// `unpack` casts without checking, so reject accounts that are not owned
+    /// by the VM program, are too short, or hold another VM account type.
+    fn check_layout(
+        &self,
+        address: &Pubkey,
+        account: &Account,
+        account_type: AccountType,
+        name: &str,
+        expected_len: usize,
+    ) -> Result<(), WalletError> {
+        if account.owner != self.program_id {
+            return Err(WalletError::InvalidAccountData {
+                account: *address,
+                reason: format!("owned by {}, expected the VM program {}", account.owner, self.program_id),
+            });
+        }
+        if account.data.len() < expected_len {
+            return Err(WalletError::AccountLayout {
+                account: *address,
+                expected_len,
+                actual_len: account.data.len(),
+            });
+        }
+        check_account_type(address, &account.data, account_type, name)
+    }
+
+    /// Fetches an account, mapping a missing account to `AccountNotFound`.
+    async fn get_existing_account(&self, address: &Pubkey) -> Result<Account, WalletError> {
+        self.fetch_account(address)
+            .await?
+            .ok_or(WalletError::AccountNotFound(*address))
+    }
+
+    async fn send_finalize_unlock_tx(&self, unlock_pda: &Pubkey, unlock_at: i64) -> Result<(), WalletError> {
+        if let Some(max_age) = self.send.max_age_slots {
+            self.check_state_fresh(unlock_pda, max_age).await?;
+        }
+        let ix = self.create_finalize_unlock_ix(unlock_pda);
+        self.process_step(unlock_pda, &[ix], None, "finalize", Some(unlock_at), "Finalize unlock").await
+    }
+
+    /// Re-reads the unlock state at the confirmation commitment just before
+    /// finalizing. The state the decision rests on must not be more than
+    /// `max_age` slots older than this read, the read must not be behind it
+    /// (as after a rollback or a lagging endpoint), and the unlock must still
+    /// be waiting with its timelock passed.
+    async fn check_state_fresh(&self, unlock_pda: &Pubkey, max_age: u64) -> Result<(), WalletError> {
+        let read_slot = self.read_slots.lock().unwrap().get(unlock_pda).copied().unwrap_or(0);
+        let stale = |reason: String| WalletError::StaleState { account: *unlock_pda, read_slot, reason };
+
+        let (slot, account) = self.rpc.get_account_with_slot(unlock_pda, self.send.confirm_commitment).await?;
+        if slot < read_slot {
+            return Err(stale(format!("the chain is now at slot {}, behind the read", slot)));
+        }
+        if slot - read_slot > max_age {
+            return Err(stale(format!(
+                "{} slots old at slot {}, more than --max-age-slots {}",
+                slot - read_slot, slot, max_age
+            )));
+        }
+
+        let account = account.ok_or_else(|| stale(format!("the unlock account no longer exists at slot {}", slot)))?;
+        self.check_layout(unlock_pda, &account, AccountType::UnlockStateAccount, "unlock state", UnlockStateAccount::get_size())?;
+        let unlock_state = UnlockStateAccount::unpack(&account.data);
+        if !unlock_state.is_waiting() {
+            return Err(stale(format!("the unlock is no longer waiting at slot {}", slot)));
+        }
+        if self.get_chain_time().await? < unlock_state.unlock_at {
+            return Err(stale(format!("the timelock has not passed at slot {}", slot)));
+        }
+        debug!("Unlock state read at slot {} still holds at slot {}", read_slot, slot);
+        Ok(())
+    }
+
+    /// Sends one unlock step unless the processed log already records it,
+    /// and logs it once it confirms. Every attempt, whatever its outcome, is
+    /// also written to the audit log. `created` is the data length of any
+    /// account the step creates at the fee payer's expense.
+    async fn process_step(
+        &self,
+        unlock_pda: &Pubkey,
+        ixs: &[Instruction],
+        created: Option<usize>,
+        step: &str,
+        unlock_at: Option<i64>,
+        label: &str,
+    ) -> Result<(), WalletError> {
+        let owner = self.owner.pubkey();
+        if let Some(path) = &self.send.processed_log {
+            if let Some(signature) = processed::lookup(path, &owner, unlock_pda, step, unlock_at)? {
+                info!("{} already recorded in {} as {}, skipping (run without --processed-log to send it anyway)", label, path, signature);
+                return self.audit(unlock_pda, step, "skipped", Some(signature), None);
+            }
+        }
+
+        let signature = match self.process_tx(unlock_pda, ixs, created, step, label).await {
+            Ok(signature) => signature,
+            Err(err) => {
+                // The step's own error matters more than a failed audit write.
+                if let Err(audit_err) = self.audit(unlock_pda, step, "failed", None, Some(err.to_string())) {
+                    warn!("Could not write the failed {} to the audit log: {}", step, audit_err);
+                }
+                return Err(err);
+            }
+        };
+        if let (Some(path), Some(signature)) = (&self.send.processed_log, signature) {
+            processed::append(path, &owner, unlock_pda, step, unlock_at, &signature)?;
+        }
+        let outcome = if signature.is_some() { "confirmed" } else { "simulated" };
+        self.audit(unlock_pda, step, outcome, signature.map(|sig| sig.to_string()), None)
+    }
+
+    /// Appends a step attempt to `--audit-log`, if one is set.
+    fn audit(
+        &self,
+        unlock_pda: &Pubkey,
+        step: &str,
+        outcome: &'static str,
+        signature: Option<String>,
+        error: Option<String>,
+    ) -> Result<(), WalletError> {
+        let Some(path) = &self.send.audit_log else {
+            return Ok(());
+        };
+        let (timelock, _) = derive_timelock(&self.timelock_params());
+        let entry = audit::Entry {
+            timestamp: Utc::now().timestamp(),
+            owner: self.owner.pubkey().to_string(),
+            fee_payer: self.fee_payer().to_string(),
+            step: step.to_string(),
+            timelock: timelock.to_string(),
+            unlock_pda: unlock_pda.to_string(),
+            outcome,
+            signature,
+            error,
+        };
+        audit::append(path, &entry)
+    }
+
+    /// Signs the instructions with payer and owner, then either sends the
+    /// transaction or, in dry-run mode, only simulates it. Returns the
+    /// confirmed signature, or `None` for a dry run.
+    async fn process_tx(
+        &self,
+        unlock_pda: &Pubkey,
+        ixs: &[Instruction],
+        created: Option<usize>,
+        step: &str,
+        label: &str,
+    ) -> Result<Option<Signature>, WalletError> {
+        let ixs = self.prepare_ixs(ixs);
+        let lookup_table = self.load_lookup_table().await?;
+        let mut resends = 0;
+
+        loop {
+            let (tx, last_valid_block_height) = self.sign_tx(&ixs, lookup_table.as_ref()).await?;
+            if resends == 0 {
+                let fee = self.check_fee(&tx, label).await?;
+                if self.send.check_balance {
+                    self.check_fee_payer_balance(fee, created).await?;
+                }
+            }
+
+            if self.send.dry_run {
+                self.simulate_tx(&tx, label).await?;
+                return Ok(None);
+            }
+            if self.send.simulate_first {
+                self.simulate_tx(&tx, label).await?;
+            }
+
+            // An expired transaction can no longer land, so re-signing the same
+            // instructions cannot apply them twice; the unlock PDA also makes
+            // both unlock steps one-shot on-chain.
+            let confirmed = send_and_confirm(
+                self.rpc.as_ref(),
+                &tx,
+                self.send.send_config,
+                label,
+                last_valid_block_height,
+                self.send.confirm_commitment,
+                self.send.confirm_timeout,
+                // Recorded before confirming, so a crash while polling still
+                // leaves the broadcast signature in the audit log.
+                |signature| {
+                    if let Err(err) = self.audit(unlock_pda, step, "sent", Some(signature.to_string()), None) {
+                        warn!("Could not write {} to the audit log: {}", signature, err);
+                    }
+                },
+            );
+            match confirmed.await {
+                Ok(sig) => {
+                    info!(
+                        "{}",
+                        output::success(format!("{} transaction successful! {}", label, output::explorer_url(&sig.to_string())))
+                    );
+                    debug!("{} signature: {}", label, sig);
+                    let confirmation = fetch_confirmation(self.rpc.as_ref(), &sig).await;
+                    log_confirmation(label, &confirmation);
+                    let result = fetch_tx_result(self.rpc.as_ref(), &sig).await;
+                    log_tx_result(label, &result);
+                    self.record_tx(label, &sig.to_string(), result, confirmation);
+                    return Ok(Some(sig));
+                }
+                // Re-signing against a pinned blockhash would only expire again.
+                Err(WalletError::BlockhashExpired { signature })
+                    if resends < self.send.max_resends && self.send.blockhash.is_none() =>
+                {
+                    resends += 1;
+                    warn!(
+                        "{} transaction {} expired, resending with a fresh blockhash ({}/{})",
+                        label, signature, resends, self.send.max_resends
+                    );
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Logs the fee the cluster quotes for the transaction, compute budget
+    /// included, and refuses to go on if it is above `--max-fee-lamports`.
+    async fn check_fee(&self, tx: &VersionedTransaction, label: &str) -> Result<u64, WalletError> {
+        let estimated = self.rpc.get_fee_for_message(&tx.message).await?;
+        info!("{} estimated fee: {} lamports", label, estimated);
+        if estimated > self.send.max_fee_lamports {
+            return Err(WalletError::FeeTooHigh {
+                estimated,
+                cap: self.send.max_fee_lamports,
+            });
+        }
+        Ok(estimated)
+    }
+
+    /// Refuses to send when the fee payer cannot cover the fee plus the
+    /// rent-exempt minimum of any account the transaction creates.
+    async fn check_fee_payer_balance(&self, fee: u64, created: Option<usize>) -> Result<(), WalletError> {
+        let rent = match created {
+            Some(len) => self.rpc.get_minimum_balance_for_rent_exemption(len).await?,
+            None => 0,
+        };
+        let payer = self.fee_payer();
+        let have = self.rpc.get_balance(&payer).await?;
+        let need = fee + rent;
+        debug!("Fee payer {} has {} lamports, needs {} ({} fee + {} rent)", payer, have, need, fee, rent);
+        if have < need {
+            return Err(WalletError::InsufficientFeePayerBalance { payer, have, need });
+        }
+        Ok(())
+    }
+
+    /// Adds the compute budget instructions and, with a durable nonce, the
+    /// nonce advance in front of the caller's instructions.
+    fn prepare_ixs(&self, ixs: &[Instruction]) -> Vec<Instruction> {
+        let mut ixs = self.with_compute_budget(ixs);
+        if let Some(memo) = &self.send.memo {
+            ixs.push(spl_memo::build_memo(memo.as_bytes(), &[&self.owner.pubkey()]));
+        }
+        if let Some(nonce) = &self.send.nonce {
+            // The runtime only honours a durable nonce when advancing it is the first instruction.
+            ixs.insert(0, system_instruction::advance_nonce_account(&nonce.account, &nonce.authority));
+        }
+        self.log_instructions(&ixs);
+        ixs
+    }
+
+    /// Prints each instruction's program, accounts (with the role each plays
+    /// in the unlock) and hex data: at debug level, or always with
+    /// `--show-instruction`.
+    fn log_instructions(&self, ixs: &[Instruction]) {
+        let level = if self.send.show_instruction { log::Level::Info } else { log::Level::Debug };
+        if !log::log_enabled!(level) {
+            return;
+        }
+
+        let (unlock_pda, _) = self.get_unlock_pda();
+        let (timelock_address, _) = derive_timelock(&self.timelock_params());
+        let mut roles = vec![
+            (self.owner.pubkey(), "owner"),
+            (self.fee_payer(), "fee_payer"),
+            (self.vm_state, "vm_state"),
+            (unlock_pda, "unlock_pda"),
+            (timelock_address, "timelock"),
+            (self.program_id, "vm_program"),
+            (solana_sdk::system_program::id(), "system_program"),
+            (sysvar::rent::id(), "rent_sysvar"),
+            (solana_sdk::compute_budget::id(), "compute_budget_program"),
+            (spl_memo::id(), "memo_program"),
+        ];
+        if let Some(nonce) = &self.send.nonce {
+            roles.push((nonce.account, "nonce_account"));
+        }
+        let role = |key: &Pubkey| {
+            let names: Vec<&str> = roles.iter().filter(|(k, _)| k == key).map(|(_, name)| *name).collect();
+            if names.is_empty() { "-".to_string() } else { names.join(", ") }
+        };
+
+        for (i, ix) in ixs.iter().enumerate() {
+            log::log!(level, "Instruction {}: program {} ({})", i, ix.program_id, role(&ix.program_id));
+            for (j, acc) in ix.accounts.iter().enumerate() {
+                log::log!(
+                    level,
+                    "  {}: {} signer={} writable={} ({})",
+                    j, acc.pubkey, acc.is_signer, acc.is_writable, role(&acc.pubkey)
+                );
+            }
+            let data: String = ix.data.iter().map(|byte| format!("{:02x}", byte)).collect();
+            log::log!(level, "  data: {}", if data.is_empty() { "(empty)" } else { data.as_str() });
+        }
+    }
+
+    /// Signs prepared instructions against a fresh blockhash (or the durable
+    /// nonce), returning the last block height at which the result can land.
+    async fn sign_tx(
+        &self,
+        ixs: &[Instruction],
+        lookup_table: Option<&AddressLookupTableAccount>,
+    ) -> Result<(VersionedTransaction, u64), WalletError> {
+        // A nonce blockhash stays valid until the nonce is advanced, so it never
+        // expires by height. A pinned blockhash's lifetime is unknown here.
+        // Given alongside a nonce account, the blockhash is the nonce value.
+        let (recent_blockhash, last_valid_block_height) = match (&self.send.nonce, self.send.blockhash) {
+            (Some(_), Some(nonce_value)) => (nonce_value, u64::MAX),
+            (Some(nonce), None) => (self.get_nonce_blockhash(nonce).await?, u64::MAX),
+            (None, Some(blockhash)) => (blockhash, u64::MAX),
+            (None, None) => self.rpc.get_latest_blockhash().await?,
+        };
+        let mut tx = self.build_tx(ixs, recent_blockhash, lookup_table)?;
+        if let Some(sponsor) = &self.send.sponsor {
+            tx = sponsor::cosign(&sponsor.url, &tx).await?;
+            debug!("Fee sponsor {} co-signed {}", sponsor.pubkey, tx.signatures[0]);
+        }
+        Ok((tx, last_valid_block_height))
+    }
+
+    /// Signs one unlock step without sending it, for `build`. Unless
+    /// `offline`, first checks that the on-chain state allows the step.
+    async fn build_step_tx(
+        &self,
+        unlock_pda: &Pubkey,
+        step: UnlockStep,
+        offline: bool,
+    ) -> Result<VersionedTransaction, WalletError> {
+        if offline && self.send.nonce.is_some() && self.send.blockhash.is_none() {
+            return Err(WalletError::Config(
+                "build --offline with --nonce-account needs the nonce value in --blockhash".to_string(),
+            ));
+        }
+        let ix = match step {
+            UnlockStep::Init => self.create_unlock_ix(unlock_pda),
+            UnlockStep::Finalize => self.create_finalize_unlock_ix(unlock_pda),
+        };
+
+        if !offline {
+            match step {
+                UnlockStep::Init if self.check_unlock_account(unlock_pda).await? => {
+                    return Err(WalletError::Config(format!("unlock account {} is already initialized", unlock_pda)));
+                }
+                UnlockStep::Init => {}
+                UnlockStep::Finalize => {
+                    let unlock_state = self.get_unlock_state(unlock_pda).await?;
+                    if !unlock_state.is_waiting() {
+                        return Err(WalletError::InvalidUnlockState);
+                    }
+                    let remaining = unlock_state.unlock_at - self.get_chain_time().await?;
+                    if remaining > 0 {
+                        warn!("Timelock has {} left; finalize will fail if submitted before then", format_remaining(remaining));
+                    }
+                }
+            }
+        }
+
+        let ixs = self.prepare_ixs(&[ix]);
+        let lookup_table = self.load_lookup_table().await?;
+        let (tx, _) = self.sign_tx(&ixs, lookup_table.as_ref()).await?;
+        Ok(tx)
+    }
+
+    /// Signs a v0 transaction against the lookup table when one is configured
+    /// and a legacy transaction otherwise.
+    fn build_tx(
+        &self,
+        ixs: &[Instruction],
+        recent_blockhash: Hash,
+        lookup_table: Option<&AddressLookupTableAccount>,
+    ) -> Result<VersionedTransaction, WalletError> {
+        let payer = self.fee_payer();
+        let message = match lookup_table {
+            Some(table) => VersionedMessage::V0(
+                v0::Message::try_compile(&payer, ixs, std::slice::from_ref(table), recent_blockhash)
+                    .map_err(|e| WalletError::TransactionBuild(e.to_string()))?,
+            ),
+            None => VersionedMessage::Legacy(Message::new_with_blockhash(ixs, Some(&payer), &recent_blockhash)),
+        };
+        let tx = if self.send.sponsor.is_some() {
+            // The sponsor adds the fee payer signature after we sign.
+            partially_sign(message, &self.signers())?
+        } else {
+            VersionedTransaction::try_new(message, &self.signers()[..])
+                .map_err(|e| WalletError::TransactionBuild(e.to_string()))?
+        };
+
+        let size = bincode::serialized_size(&tx).map_err(|e| WalletError::TransactionBuild(e.to_string()))?;
+        if size > PACKET_DATA_SIZE as u64 {
+            return Err(WalletError::TransactionBuild(format!(
+                "transaction is {} bytes, over the {}-byte limit; shorten --memo",
+                size, PACKET_DATA_SIZE
+            )));
+        }
+        Ok(tx)
+    }
+
+    /// Reads the stored blockhash from an initialized nonce account owned by
+    /// the configured authority.
+    async fn get_nonce_blockhash(&self, nonce: &NonceConfig) -> Result<Hash, WalletError> {
+        let account = self.get_existing_account(&nonce.account).await?;
+        let data = nonce_utils::data_from_account(&account).map_err(|e| WalletError::InvalidAccountData {
+            account: nonce.account,
+            reason: e.to_string(),
+        })?;
+        if data.authority != nonce.authority {
+            return Err(WalletError::Config(format!(
+                "nonce account {} is controlled by {}, not {}",
+                nonce.account, data.authority, nonce.authority
+            )));
+        }
+        Ok(data.blockhash())
+    }
+
+    /// Fetches the address lookup table named by `--lookup-table`, if any.
+    async fn load_lookup_table(&self) -> Result<Option<AddressLookupTableAccount>, WalletError> {
+        let Some(key) = self.send.lookup_table else {
+            return Ok(None);
+        };
+        let account = self.get_existing_account(&key).await?;
+        let table = AddressLookupTable::deserialize(&account.data).map_err(|e| WalletError::InvalidAccountData {
+            account: key,
+            reason: e.to_string(),
+        })?;
+        debug!("Using lookup table {} with {} addresses", key, table.addresses.len());
+        Ok(Some(AddressLookupTableAccount {
+            key,
+            addresses: table.addresses.to_vec(),
+        }))
+    }
+
+    fn record_tx(&self, label: &str, signature: &str, result: TransactionResult, confirmation: Confirmation) {
+        self.transactions.lock().unwrap().push(TxRecord {
+            label: label.to_string(),
+            signature: signature.to_string(),
+            explorer_url: output::explorer_url(signature),
+            result,
+            confirmation,
+        });
+    }
+
+    /// Transactions confirmed so far, with their fees and the time taken.
+    pub fn report(&self, unlock_pda: &Pubkey) -> Report {
+        let transactions = self.transactions.lock().unwrap().clone();
+        Report {
+            owner: self.owner.pubkey().to_string(),
+            unlock_pda: unlock_pda.to_string(),
+            fees_lamports: output::total_fees(&transactions),
+            duration_secs: self.started.elapsed().as_secs_f64(),
+            transactions,
+        }
+    }
+
+    /// Pays the fees: the sponsor if one is configured, else the payer
+    /// keypair, else the owner.
+    fn fee_payer(&self) -> Pubkey {
+        match (&self.send.sponsor, &self.payer) {
+            (Some(sponsor), _) => sponsor.pubkey,
+            (None, Some(payer)) => payer.pubkey(),
+            (None, None) => self.owner.pubkey(),
+        }
+    }
+
+    /// Transaction signers, with the owner listed once when it is also the payer.
+    fn signers(&self) -> Vec<&dyn Signer> {
+        match &self.payer {
+            Some(payer) if payer.pubkey() != self.owner.pubkey() => vec![payer as &dyn Signer, self.owner.as_ref()],
+            _ => vec![self.owner.as_ref()],
+        }
+    }
+
+    /// Prepends the compute budget instructions requested on the command line.
+    fn with_compute_budget(&self, ixs: &[Instruction]) -> Vec<Instruction> {
+        let mut all = Vec::with_capacity(ixs.len() + 2);
+        if let Some(limit) = self.send.compute_limit {
+            all.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+        }
+        if self.send.priority_fee > 0 {
+            all.push(ComputeBudgetInstruction::set_compute_unit_price(self.send.priority_fee));
+        }
+        all.extend_from_slice(ixs);
+        all
+    }
+
+    async fn simulate_tx(&self, tx: &VersionedTransaction, label: &str) -> Result<(), WalletError> {
+        let result = self.rpc.simulate_transaction(tx).await?;
+
+        info!("Simulation logs:");
+        for line in result.logs.unwrap_or_default() {
+            info!("  {}", line);
+        }
+        if let Some(units) = result.units_consumed {
+            info!("Compute units consumed: {}", units);
+        }
+
+        if let Some(err) = result.err {
+            return Err(WalletError::SimulationFailed {
+                label: label.to_string(),
+                reason: err.to_string(),
+            });
+        }
+
+        if self.send.dry_run {
+            info!("{} simulation succeeded (dry run, nothing sent)", label);
+        } else {
+            info!("{} simulation succeeded, sending", label);
+        }
+        Ok(())
+    }
+
+    /// Reads the cluster's unix timestamp from the clock sysvar.
+    async fn get_chain_time(&self) -> Result<i64, WalletError> {
+        Ok(self.get_clock().await?.unix_timestamp)
+    }
+
+    async fn get_clock(&self) -> Result<Clock, WalletError> {
+        let clock_id = sysvar::clock::id();
+        let clock_account = self.get_existing_account(&clock_id).await?;
+        let mut lamports = clock_account.lamports;
+        let mut data = clock_account.data.clone();
+        let clock_info = AccountInfo::new(
+            &clock_id,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &clock_account.owner,
+            clock_account.executable,
+            clock_account.rent_epoch,
+        );
+        Clock::from_account_info(&clock_info).map_err(|e| WalletError::InvalidAccountData {
+            account: clock_id,
+            reason: e.to_string(),
+        })
+    }
+
+    /// Average seconds per slot over the cluster's recent performance
+    /// samples. Only used for estimates, so failures are logged and `None`.
+    async fn average_slot_time(&self) -> Option<f64> {
+        let samples = match self.rpc.get_recent_performance_samples(SLOT_TIME_SAMPLES).await {
+            Ok(samples) => samples,
+            Err(err) => {
+                debug!("Could not fetch performance samples: {}", err);
+                return None;
+            }
+        };
+        let slots: u64 = samples.iter().map(|sample| sample.num_slots).sum();
+        let seconds: u64 = samples.iter().map(|sample| u64::from(sample.sample_period_secs)).sum();
+        (slots > 0).then(|| seconds as f64 / slots as f64)
+    }
+
+    /// Reads the unlock account and chain clock to describe where the
+    /// timelock stands and what to run next.
+    pub async fn status(&self, unlock_pda: &Pubkey) -> Result<StatusReport, WalletError> {
+        let pdas = derive_unlock_pdas(&self.timelock_params(), &self.vm_state);
+
+        let (state, unlock_at, next_action) = if !self.check_unlock_account(unlock_pda).await? {
+            ("locked", None, "run `unlock` to start the timelock")
+        } else {
+            let unlock_state = self.get_unlock_state(unlock_pda).await?;
+            if unlock_state.is_unlocked() {
+                ("unlocked", Some(unlock_state.unlock_at), "nothing to do, the account is unlocked")
+            } else if unlock_state.is_waiting() {
+                ("waiting", Some(unlock_state.unlock_at), "run without a subcommand to wait and finalize")
+            } else {
+                return Err(WalletError::InvalidUnlockState);
+            }
+        };
+
+        let clock = self.get_clock().await?;
+        let seconds_remaining = match (state, unlock_at) {
+            ("waiting", Some(unlock_at)) => Some((unlock_at - clock.unix_timestamp).max(0)),
+            _ => None,
+        };
+        // The VM times unlocks by the clock's unix timestamp, not by slot, so
+        // the unlock slot is only an estimate from recent slot times.
+        let estimated_unlock_slot = match seconds_remaining {
+            Some(remaining) => self
+                .average_slot_time()
+                .await
+                .map(|slot_time| clock.slot + (remaining as f64 / slot_time).ceil() as u64),
+            None => None,
+        };
+
+        Ok(StatusReport {
+            owner: pdas.owner.to_string(),
+            timelock_address: pdas.timelock_address.to_string(),
+            unlock_pda: unlock_pda.to_string(),
+            state,
+            unlock_at,
+            seconds_remaining,
+            current_slot: clock.slot,
+            estimated_unlock_slot,
+            next_action,
+        })
+    }
+
+    /// Polls until the timelock passes and then finalizes. Sleeps at most
+    /// `--watch-interval` at a time so chain clock drift is picked up, and
+    /// gives up after `--watch-timeout`. Pass `just_initialized` right after
+    /// sending the unlock: until the new account shows up at the read
+    /// commitment (or `--confirm-timeout` passes) a missing account means
+    /// "not visible yet" rather than a cancelled unlock.
+    pub async fn wait_for_unlock(&self, unlock_pda: &Pubkey, just_initialized: bool) -> Result<(), WalletError> {
+        let deadline = self.wait.timeout.map(|timeout| Instant::now() + timeout);
+        let mut visible_by = just_initialized.then(|| Instant::now() + self.send.confirm_timeout);
+        loop {
+            let unlock_state = match self.get_unlock_state(unlock_pda).await {
+                Err(WalletError::AccountNotFound(_)) => match visible_by {
+                    Some(visible_by) if Instant::now() < visible_by => {
+                        debug!("Unlock account {} not visible at the read commitment yet", unlock_pda);
+                        tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+                        continue;
+                    }
+                    _ => return Err(WalletError::UnlockCancelled(*unlock_pda)),
+                },
+                result => result?,
+            };
+            visible_by = None;
+
+            if unlock_state.is_unlocked() {
+                info!("Account is already unlocked!");
+                return Ok(());
+            }
+
+            if !unlock_state.is_waiting() {
+                return Err(WalletError::InvalidUnlockState);
+            }
+
+            let current_time = self.get_chain_time().await?;
+
+            if current_time >= unlock_state.unlock_at {
+                info!("Timelock duration has passed, proceeding with finalization");
+                return self.send_finalize_unlock_tx(unlock_pda, unlock_state.unlock_at).await;
+            }
+
+            info!(
+                "Waiting for timelock...\nCurrent time: {} ({})\nUnlock at: {} ({})\nAccount unlocks in {}", 
+                current_time, format_timestamp(current_time),
+                unlock_state.unlock_at, format_timestamp(unlock_state.unlock_at),
+                format_remaining(unlock_state.unlock_at - current_time)
+            );
+
+            if self.send.dry_run {
+                info!("Dry run: timelock still active, nothing to simulate yet");
+                return Err(WalletError::NotUnlocked { unlock_at: unlock_state.unlock_at });
+            }
+
+            let remaining = Duration::from_secs((unlock_state.unlock_at - current_time) as u64);
+            let mut nap = self.wait.interval.min(remaining);
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(WalletError::WatchTimeout(self.wait.timeout.unwrap_or_default()));
+                }
+                nap = nap.min(left);
+            }
+            tokio::time::sleep(nap).await;
+        }
+    }
+    
+}
+
+/// How a successful run ended, so schedulers can tell a no-op apart.
+pub enum Outcome {
+    /// The command ran, sending whatever it needed to
+    Done,
+    /// A sending command found every step already done and sent nothing
+    NothingToDo,
+}
+
+/// Interrupting a run can drop a transaction between broadcast and
+/// confirmation, so say exactly which ones may still land.
+pub fn report_interrupt() {
+    let in_flight = IN_FLIGHT.lock().unwrap();
+    if in_flight.is_empty() {
+        warn!("Interrupted; no transaction was awaiting confirmation");
+        return;
+    }
+    for signature in in_flight.iter() {
+        warn!(
+            "Interrupted while confirming {}; it was already broadcast and may still land. Check it with `solana confirm {}` or {}",
+            signature, signature, output::explorer_url(&signature.to_string())
+        );
+    }
+    warn!("Run `status` to see the unlock state; a step that landed will be detected on-chain and not sent twice");
+}
+
+/// Interactive input a run may need. The binary asks on the terminal;
+/// embedders answer however suits them, or fail the run.
+pub trait Prompt: Sync {
+    /// The 12-word mnemonic to create a missing owner keyfile from.
+    fn mnemonic(&self) -> Result<String, WalletError>;
+
+    /// Whether to go ahead after showing `summary`, one detail per line.
+    /// `false` aborts the run.
+    fn proceed(&self, summary: &[String]) -> Result<bool, WalletError>;
+}
+
+/// Runs the command `args` selects, as the `vm_wallet` binary does, asking
+/// `prompt` for anything interactive. Logging and output mode are left to
+/// the caller.
+pub async fn run(args: Args, prompt: &dyn Prompt) -> Result<Outcome, WalletError> {
+    // Key management needs neither a network config nor an existing owner key.
+    match &args.command {
+        Some(Command::EncryptKey { input, output }) => {
+            encrypt_keyfile(input, output.as_deref().unwrap_or(input), args.passphrase_env.as_deref())?;
+            return Ok(Outcome::Done);
+        }
+        Some(Command::GenerateKey { output: path, format, force }) => {
+            let pubkey = generate_keyfile(path, *format, *force)?;
+            if output::json_mode() {
+                output::print_json(&serde_json::json!({ "pubkey": pubkey.to_string(), "path": path }));
+            } else {
+                info!("Generated keypair {} at {}", pubkey, path);
+            }
+            return Ok(Outcome::Done);
+        }
+        _ => {}
+    }
+
+    let config = WalletConfig::resolve(&args)?;
+    output::set_explorer(args.output_explorer, &config.network);
+    let handled = match &args.command {
+        Some(Command::Pdas { owner }) => Some(print_pdas(&args, &config, owner.as_deref(), prompt)),
+        Some(Command::Doctor) => Some(doctor::run(&config).await),
+        Some(Command::ExportPubkey { key, ata }) => Some(export_pubkey(&args, &config, key.as_deref(), *ata, prompt)),
+        // Submitting needs only the signed transaction, not the keys that signed it.
+        Some(Command::Submit { input, encoding }) => Some(submit_tx(&args, &config, input, *encoding, prompt).await),
+        _ => None,
+    };
+    if let Some(result) = handled {
+        return result.map(|()| Outcome::Done);
+    }
+
+    let sends = matches!(args.command, Some(Command::Unlock) | Some(Command::Finalize) | None);
+    if let Some(dir) = &args.owners_dir {
+        if !sends {
+            return Err(WalletError::Config(
+                "--owners-dir only supports unlock, finalize and the full unlock".to_string(),
+            ));
+        }
+        return run_batch(&args, &config, dir, prompt).await;
+    }
+
+    let owner = load_owner(&args, &config, prompt)?;
+    // Reading the unlock state signs nothing, so it needs no fee payer.
+    let payer = match args.command {
+        Some(Command::Status) => None,
+        _ => load_payer(&args, &config)?,
+    };
+    let (context, unlock_pda) = build_context(&args, &config, owner, payer)?;
+
+    let reads_chain = match args.command {
+        Some(Command::Build { offline, .. }) => !offline,
+        _ => true,
+    };
+    if reads_chain {
+        context.preflight(&unlock_pda).await?;
+    }
+
+    if sends && !args.yes && !args.dry_run && config.network == "mainnet" {
+        confirm_send(&config, &context, &unlock_pda, prompt)?;
+    }
+
+    if let Some(Command::Status) = args.command {
+        print_status(&context.status(&unlock_pda).await?)?;
+        return Ok(Outcome::Done);
+    }
+    run_command(&args, &context, &unlock_pda).await?;
+
+    let report = context.report(&unlock_pda);
+    if output::json_mode() {
+        output::print_json(&report);
+    } else {
+        info!(
+            "Summary: {} transactions, {} lamports in fees, {:.1}s",
+            report.transactions.len(), report.fees_lamports, report.duration_secs
+        );
+    }
+
+    if sends && !args.dry_run && report.transactions.is_empty() {
+        info!("Nothing to do, no transaction was needed");
+        return Ok(Outcome::NothingToDo);
+    }
+    Ok(Outcome::Done)
+}
+
+/// Runs the selected signing command for one owner's context.
+async fn run_command(args: &Args, context: &UnlockContext, unlock_pda: &Pubkey) -> Result<(), WalletError> {
+    match args.command {
+        Some(Command::Unlock) => context.initiate_unlock(unlock_pda).await?,
+        Some(Command::Finalize) => context.finalize_unlock(unlock_pda).await?,
+        Some(Command::Build { step, output: ref path, encoding, offline }) => {
+            let tx = context.build_step_tx(unlock_pda, step, offline).await?;
+            txfile::write_tx(path, &tx, encoding)?;
+            info!("Signed {:?} transaction {} written to {}", step, tx.signatures[0], path);
+        }
+        Some(Command::Status)
+        | Some(Command::Doctor)
+        | Some(Command::ExportPubkey { .. })
+        | Some(Command::Pdas { .. })
+        | Some(Command::Submit { .. })
+        | Some(Command::EncryptKey { .. })
+        | Some(Command::GenerateKey { .. }) => unreachable!("handled before running a command"),
+        None => run_full_unlock(context, unlock_pda).await?,
+    }
+    Ok(())
+}
+
+/// Processes every keyfile in `dir` as its own owner, `--concurrency` at a
+/// time. Each owner gets a separate context, so one failure is recorded in
+/// the report and the rest carry on.
+async fn run_batch(args: &Args, config: &WalletConfig, dir: &str, prompt: &dyn Prompt) -> Result<Outcome, WalletError> {
+    let mut keyfiles: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| path.display().to_string())
+        .collect();
+    keyfiles.sort();
+    if keyfiles.is_empty() {
+        return Err(WalletError::Config(format!("no .json keyfiles found in {}", dir)));
+    }
+
+    let started = Instant::now();
+    let payer = load_payer(args, config)?;
+    if !args.yes && !args.dry_run && config.network == "mainnet" {
+        proceed(prompt, &[
+            format!("Network: {}", config.network),
+            format!("Owners: {} keyfiles in {}", keyfiles.len(), dir),
+        ])?;
+    }
+
+    let mut results: Vec<OwnerResult> = futures::stream::iter(keyfiles)
+        .map(|keyfile| {
+            let payer = payer.as_ref().map(Keypair::insecure_clone);
+            async move { process_owner(args, config, keyfile, payer).await }
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_by(|a, b| a.keyfile.cmp(&b.keyfile));
+    let failed = results.iter().filter(|result| !result.ok).count();
+    let report = BatchReport {
+        succeeded: results.len() - failed,
+        failed,
+        fees_lamports: results.iter().map(|result| output::total_fees(&result.transactions)).sum(),
+        duration_secs: started.elapsed().as_secs_f64(),
+        owners: results,
+    };
+
+    if output::json_mode() {
+        output::print_json(&report);
+    } else {
+        for result in &report.owners {
+            let owner = result.owner.as_deref().unwrap_or("-");
+            match &result.error {
+                None => info!("ok     {} ({}), {} transactions", owner, result.keyfile, result.transactions.len()),
+                Some(e) => info!("failed {} ({}): {}", owner, result.keyfile, e),
+            }
+        }
+        info!(
+            "Summary: {} succeeded, {} failed, {} lamports in fees, {:.1}s",
+            report.succeeded, report.failed, report.fees_lamports, report.duration_secs
+        );
+    }
+
+    if failed > 0 {
+        return Err(WalletError::BatchFailed { failed, total: report.owners.len() });
+    }
+    if !args.dry_run && report.owners.iter().all(|result| result.transactions.is_empty()) {
+        info!("Nothing to do for any owner, no transaction was needed");
+        return Ok(Outcome::NothingToDo);
+    }
+    Ok(Outcome::Done)
+}
+
+async fn process_owner(args: &Args, config: &WalletConfig, keyfile: String, payer: Option<Keypair>) -> OwnerResult {
+    let mut result = OwnerResult {
+        keyfile,
+        owner: None,
+        ok: false,
+        error: None,
+        transactions: Vec::new(),
+    };
+    let owner = match load_keypair_from_file(&result.keyfile, &key_options(args)) {
+        Ok(owner) => owner,
+        Err(e) => {
+            warn!("Skipping {}: {}", result.keyfile, e);
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.owner = Some(owner.pubkey().to_string());
+
+    let outcome = match build_context(args, config, Box::new(owner), payer) {
+        Ok((context, unlock_pda)) => {
+            let outcome = match context.preflight(&unlock_pda).await {
+                Ok(()) => run_command(args, &context, &unlock_pda).await,
+                Err(e) => Err(e),
+            };
+            result.transactions = context.report(&unlock_pda).transactions;
+            outcome
+        }
+        Err(e) => Err(e),
+    };
+    match outcome {
+        Ok(()) => result.ok = true,
+        // An owner still in its timelock has nothing to do yet, which is no failure.
+        Err(e @ WalletError::NotUnlocked { .. }) => {
+            info!("Owner {}: {}", result.owner.as_deref().unwrap_or_default(), e);
+            result.ok = true;
+        }
+        Err(e) => {
+            warn!("Owner {} failed: {}", result.owner.as_deref().unwrap_or_default(), e);
+            result.error = Some(e.to_string());
+        }
+    }
+    result
+}
+
+/// Loads the fee payer keypair. Without a payer keyfile, or with a sponsor,
+/// there is none and the owner or sponsor pays.
+fn load_payer(args: &Args, config: &WalletConfig) -> Result<Option<Keypair>, WalletError> {
+    if args.sponsor_url.is_some() {
+        Ok(None)
+    } else if let Some(var) = &args.payer_key_env {
+        Ok(Some(load_keypair_from_env(var)?))
+    } else if args.payer_key.is_some()
+        || config.payer_key_path == STDIN_PATH
+        || std::path::Path::new(&config.payer_key_path).exists()
+    {
+        Ok(Some(load_keypair_from_file(&config.payer_key_path, &key_options(args))?))
+    } else {
+        info!("No payer keyfile found, the owner will pay transaction fees");
+        Ok(None)
+    }
+}
+
+/// Common setup for every command that signs or reads unlock state: builds
+/// the sending options and RPC client for the given owner and fee payer, and
+/// checks the derived unlock PDA.
+pub fn build_context(
+    args: &Args,
+    config: &WalletConfig,
+    owner: Box<dyn Signer>,
+    payer: Option<Keypair>,
+) -> Result<(UnlockContext, Pubkey), WalletError> {
+    let sponsor = match (&args.sponsor_url, &args.sponsor_pubkey) {
+        (Some(url), Some(pubkey)) => Some(SponsorConfig {
+            url: url.clone(),
+            pubkey: parse_pubkey("sponsor-pubkey", pubkey)?,
+        }),
+        _ => None,
+    };
+    let fee_payer = match &sponsor {
+        Some(sponsor) => sponsor.pubkey,
+        None => payer.as_ref().map_or(owner.pubkey(), Signer::pubkey),
+    };
+    
+    let send = SendOptions {
+        dry_run: args.dry_run,
+        simulate_first: args.simulate_first,
+        priority_fee: args.priority_fee,
+        compute_limit: args.compute_limit,
+        send_config: send_config(args)?,
+        confirm_commitment: confirm_commitment(args, config)?,
+        confirm_timeout: Duration::from_secs(args.confirm_timeout),
+        max_resends: args.max_resends,
+        max_fee_lamports: args.max_fee_lamports,
+        lookup_table: args.lookup_table.as_deref()
+            .map(|table| parse_pubkey("lookup-table", table))
+            .transpose()?,
+        nonce: match &args.nonce_account {
+            Some(account) => Some(NonceConfig {
+                account: parse_pubkey("nonce-account", account)?,
+                authority: match &args.nonce_authority {
+                    Some(authority) => parse_pubkey("nonce-authority", authority)?,
+                    None => fee_payer,
+                },
+            }),
+            None => None,
+        },
+        blockhash: args.blockhash.as_deref()
+            .map(|hash| {
+                Hash::from_str(hash)
+                    .map_err(|e| WalletError::Config(format!("invalid --blockhash '{}': {}", hash, e)))
+            })
+            .transpose()?,
+        max_age_slots: args.max_age_slots,
+        show_instruction: args.show_instruction,
+        check_balance: args.fee_payer_balance_check,
+        memo: args.memo.clone(),
+        sponsor,
+        processed_log: args.processed_log.clone(),
+        audit_log: args.audit_log.clone(),
+    };
+    let wait = WaitOptions {
+        interval: Duration::from_secs(args.watch_interval.max(1)),
+        timeout: args.watch_timeout.map(Duration::from_secs),
+    };
+    let context = UnlockContext::new(config, owner, payer, send, wait)?;
+    
+    // Get and verify the PDA
+    let (unlock_pda, _) = context.get_unlock_pda();
+    if !context.verify_unlock_pda(&unlock_pda)? {
+        return Err(WalletError::PdaVerificationFailed(unlock_pda));
+    }
+    
+    info!("PDA verification passed, checking unlock status...");
+
+    Ok((context, unlock_pda))
+}
+
+/// Commitment a sent transaction must reach; `--await-finalization` holds
+/// out for finalized whatever the configured send commitment is.
+fn confirm_commitment(args: &Args, config: &WalletConfig) -> Result<CommitmentConfig, WalletError> {
+    if args.await_finalization {
+        return Ok(CommitmentConfig::finalized());
+    }
+    config.send_commitment_config()
+}
+
+/// Preflight settings for broadcasting. Without `--preflight-commitment` the
+/// RPC pool simulates at its read commitment.
+fn send_config(args: &Args) -> Result<RpcSendTransactionConfig, WalletError> {
+    Ok(RpcSendTransactionConfig {
+        skip_preflight: args.skip_preflight,
+        preflight_commitment: args.preflight_commitment.as_deref()
+            .map(|level| parse_commitment(level).map(|commitment| commitment.commitment))
+            .transpose()?,
+        ..RpcSendTransactionConfig::default()
+    })
+}
+
+fn key_options(args: &Args) -> KeyLoadOptions<'_> {
+    KeyLoadOptions {
+        passphrase_env: args.passphrase_env.as_deref(),
+        strict_permissions: args.strict_permissions,
+    }
+}
+
+fn load_owner(args: &Args, config: &WalletConfig, prompt: &dyn Prompt) -> Result<Box<dyn Signer>, WalletError> {
+    #[cfg(feature = "ledger")]
+    if let Some(path) = &args.owner_ledger {
+        return keys::load_ledger_signer(path);
+    }
+    if let Some(var) = &args.owner_key_env {
+        return Ok(Box::new(load_keypair_from_env(var)?));
+    }
+    let keypair = match &args.owner_mnemonic {
+        Some(phrase) => load_keypair_from_mnemonic(
+            phrase,
+            args.owner_passphrase.as_deref().unwrap_or(""),
+            args.derivation_path.as_deref(),
+        )?,
+        None => {
+            // First check if the owner keyfile exists
+            if config.owner_key_path != STDIN_PATH && !std::path::Path::new(&config.owner_key_path).exists() {
+                save_mnemonic_keypair(&config.owner_key_path, &prompt.mnemonic()?)?;
+            }
+            load_keypair_from_file(&config.owner_key_path, &key_options(args))?
+        }
+    };
+    Ok(Box::new(keypair))
+}
+
+/// Prints the derived PDAs for an owner without touching the network.
+fn print_pdas(args: &Args, config: &WalletConfig, owner: Option<&str>, prompt: &dyn Prompt) -> Result<(), WalletError> {
+    let owner = match owner {
+        Some(owner) => parse_pubkey("owner", owner)?,
+        None => load_owner(args, config, prompt)?.pubkey(),
+    };
+
+    let timelock = TimelockParams {
+        mint: parse_pubkey("mint", &config.mint)?,
+        vm_authority: parse_pubkey("vm-authority", &config.vm_authority)?,
+        owner,
+        lock_duration: config.lock_duration,
+    };
+    let pdas = derive_unlock_pdas(&timelock, &parse_pubkey("vm-state", &config.vm_state)?);
+
+    if output::json_mode() {
+        output::print_json(&pdas);
+    } else {
+        info!("Owner: {}", pdas.owner);
+        info!("Timelock address: {} (bump {})", pdas.timelock_address, pdas.timelock_bump);
+        info!("Unlock PDA: {} (bump {})", pdas.unlock_pda, pdas.unlock_bump);
+    }
+    Ok(())
+}
+
+fn export_pubkey(
+    args: &Args,
+    config: &WalletConfig,
+    key: Option<&str>,
+    ata: bool,
+    prompt: &dyn Prompt,
+) -> Result<(), WalletError> {
+    let pubkey = match key {
+        Some(path) => load_keypair_from_file(path, &key_options(args))?.pubkey(),
+        None => load_owner(args, config, prompt)?.pubkey(),
+    };
+    let token_account = if ata {
+        Some(derive_associated_token_account(&pubkey, &parse_pubkey("mint", &config.mint)?))
+    } else {
+        None
+    };
+    if output::json_mode() {
+        let mut json = serde_json::json!({ "pubkey": pubkey.to_string() });
+        if let Some(token_account) = token_account {
+            json["associated_token_account"] = token_account.to_string().into();
+            json["mint"] = config.mint.clone().into();
+        }
+        output::print_json(&json);
+    } else {
+        info!("{}", pubkey);
+        if let Some(token_account) = token_account {
+            info!("Associated token account for {}: {}", config.mint, token_account);
+        }
+    }
+    Ok(())
+}
+
+/// Broadcasts a transaction file from `build`. Its blockhash lifetime is
+/// unknown here, so only `--confirm-timeout` bounds the wait.
+async fn submit_tx(
+    args: &Args,
+    config: &WalletConfig,
+    input: &str,
+    encoding: TxEncoding,
+    prompt: &dyn Prompt,
+) -> Result<(), WalletError> {
+    let tx = txfile::read_tx(input, encoding)?;
+    if !args.yes && config.network == "mainnet" {
+        proceed(prompt, &[
+            format!("Network: {}", config.network),
+            format!("Transaction: {}", tx.signatures[0]),
+            format!("Fee payer: {}", tx.message.static_account_keys()[0]),
+        ])?;
+    }
+    let rpc = rpc_pool(config)?;
+    let timeout = Duration::from_secs(args.confirm_timeout);
+
+    let commitment = confirm_commitment(args, config)?;
+    let signature = send_and_confirm(&rpc, &tx, send_config(args)?, "Submitted", u64::MAX, commitment, timeout, |_| {}).await?;
+    info!(
+        "{}",
+        output::success(format!("Submitted transaction successful! {}", output::explorer_url(&signature.to_string())))
+    );
+    let confirmation = fetch_confirmation(&rpc, &signature).await;
+    let result = fetch_tx_result(&rpc, &signature).await;
+    if output::json_mode() {
+        output::print_json(&TxRecord {
+            label: "Submitted".to_string(),
+            signature: signature.to_string(),
+            explorer_url: output::explorer_url(&signature.to_string()),
+            result,
+            confirmation,
+        });
+    } else {
+        info!("Signature: {}", signature);
+        log_confirmation("Submitted", &confirmation);
+        log_tx_result("Submitted", &result);
+    }
+    Ok(())
+}
+
+/// Asks `prompt` before sending real transactions; declining aborts.
+fn confirm_send(
+    config: &WalletConfig,
+    context: &UnlockContext,
+    unlock_pda: &Pubkey,
+    prompt: &dyn Prompt,
+) -> Result<(), WalletError> {
+    proceed(prompt, &[
+        format!("Network: {}", config.network),
+        format!("Owner: {}", context.owner.pubkey()),
+        format!("Fee payer: {}", context.fee_payer()),
+        format!("Unlock PDA: {}", unlock_pda),
+    ])
+}
+
+fn proceed(prompt: &dyn Prompt, summary: &[String]) -> Result<(), WalletError> {
+    if prompt.proceed(summary)? {
+        Ok(())
+    } else {
+        Err(WalletError::Aborted)
+    }
+}
+
+fn print_status(status: &StatusReport) -> Result<(), WalletError> {
+    if output::json_mode() {
+        output::print_json(status);
+        return Ok(());
+    }
+
+    info!("Owner: {}", status.owner);
+    info!("Timelock address: {}", status.timelock_address);
+    info!("Unlock PDA: {}", status.unlock_pda);
+    info!("State: {}", status.state);
+    if let Some(unlock_at) = status.unlock_at {
+        info!("Unlock at: {} ({})", unlock_at, format_timestamp(unlock_at));
+    }
+    if let Some(remaining) = status.seconds_remaining {
+        info!("Unlocks in: {}", format_remaining(remaining));
+    }
+    info!("Current slot: {}", status.current_slot);
+    if let Some(slot) = status.estimated_unlock_slot {
+        info!("Estimated unlock slot: {}", slot);
+    }
+    info!("Next: {}", status.next_action);
+    Ok(())
+}
+
+/// Runs the whole lifecycle: initialise the unlock if needed, wait out the
+/// timelock and finalise.
+async fn run_full_unlock(context: &UnlockContext, unlock_pda: &Pubkey) -> Result<(), WalletError> {
+    // Check if unlock account exists before initializing
+    if context.check_unlock_account(unlock_pda).await? {
+        info!("Unlock account already initialized, proceeding to wait for unlock");
+        context.wait_for_unlock(unlock_pda, false).await?;
+    } else {
+        info!("Initializing new unlock...");
+        context.send_unlock_tx().await?;
+        if context.send.dry_run {
+            info!("Dry run: unlock not sent, skipping the timelock wait");
+            return context.report_estimated_unlock_at().await;
+        }
+        info!("Unlock initialized, waiting for timelock duration...");
+        context.wait_for_unlock(unlock_pda, true).await?;
+    }
+
+    if context.send.dry_run {
+        info!("Dry run completed, no transactions were sent");
+    } else {
+        info!("{}", output::success("Unlock process completed successfully!"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::mock::MockRpc;
+
+    fn context(owner: &Keypair, rpc: MockRpc) -> UnlockContext {
+        let owner = Keypair::from_bytes(&owner.to_bytes()).unwrap();
+        UnlockContext::with_rpc(
+            Box::new(rpc),
+            &WalletConfig::default(),
+            Box::new(owner),
+            None,
+            SendOptions::default(),
+            WaitOptions::default(),
+        )
+        .unwrap()
+    }
+
+    fn unlock_pda(owner: &Keypair) -> Pubkey {
+        context(owner, MockRpc::default()).get_unlock_pda().0
+    }
+
+    /// An unlock state account owned by the VM program with every other
+    /// field zeroed, so it is neither waiting nor unlocked.
+    fn blank_unlock_state(account_type: AccountType) -> Account {
+        let mut data = vec![0; UnlockStateAccount::get_size()];
+        data[0] = account_type as u8;
+        Account {
+            lamports: 1,
+            data,
+            owner: Pubkey::from_str(VM_PROGRAM_ID).unwrap(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn unlock_instructions_target_the_derived_accounts() {
+        let owner = Keypair::new();
+        let context = context(&owner, MockRpc::default());
+        let (unlock_pda, _) = context.get_unlock_pda();
+        let vm_state = Pubkey::from_str(&WalletConfig::default().vm_state).unwrap();
+
+        let init = context.create_unlock_ix(&unlock_pda);
+        assert_eq!(init, timelock_unlock_init(owner.pubkey(), owner.pubkey(), vm_state, unlock_pda));
+
+        let finalize = context.create_finalize_unlock_ix(&unlock_pda);
+        assert_eq!(finalize, timelock_unlock_finalize(owner.pubkey(), owner.pubkey(), vm_state, unlock_pda));
+        assert!(finalize.accounts.iter().any(|meta| meta.pubkey == owner.pubkey() && meta.is_signer));
+        assert!(finalize.accounts.iter().any(|meta| meta.pubkey == unlock_pda && meta.is_writable));
+    }
+
+    #[test]
+    fn derived_unlock_pda_verifies() {
+        let context = context(&Keypair::new(), MockRpc::default());
+        let (unlock_pda, _) = context.get_unlock_pda();
+        assert!(context.verify_unlock_pda(&unlock_pda).unwrap());
+        assert!(!context.verify_unlock_pda(&Pubkey::new_unique()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn finalize_rejects_an_unlock_that_is_not_waiting() {
+        let owner = Keypair::new();
+        let unlock_pda = unlock_pda(&owner);
+        let rpc = MockRpc::default().with_account(unlock_pda, blank_unlock_state(AccountType::UnlockStateAccount));
+        let context = context(&owner, rpc);
+
+        let err = context.finalize_unlock(&unlock_pda).await.unwrap_err();
+        assert!(matches!(err, WalletError::InvalidUnlockState), "{:?}", err);
+        assert!(context.report(&unlock_pda).transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn status_reads_the_unlock_account_once() {
+        let owner = Keypair::new();
+        let unlock_pda = unlock_pda(&owner);
+        let rpc = MockRpc::default().with_account(unlock_pda, blank_unlock_state(AccountType::UnlockStateAccount));
+        let reads = rpc.reads();
+        let context = context(&owner, rpc);
+
+        // Blank state is neither waiting nor unlocked, after the one read.
+        let err = context.status(&unlock_pda).await.unwrap_err();
+        assert!(matches!(err, WalletError::InvalidUnlockState), "{:?}", err);
+        assert_eq!(reads.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn finalize_without_an_unlock_account_fails() {
+        let context = context(&Keypair::new(), MockRpc::default());
+        let (unlock_pda, _) = context.get_unlock_pda();
+
+        let err = context.finalize_unlock(&unlock_pda).await.unwrap_err();
+        assert!(matches!(err, WalletError::AccountNotFound(address) if address == unlock_pda), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn unlock_account_of_another_type_is_rejected() {
+        let owner = Keypair::new();
+        let unlock_pda = unlock_pda(&owner);
+        let rpc = MockRpc::default().with_account(unlock_pda, blank_unlock_state(AccountType::CodeVmAccount));
+        let context = context(&owner, rpc);
+
+        let err = context.finalize_unlock(&unlock_pda).await.unwrap_err();
+        assert!(matches!(err, WalletError::InvalidAccountData { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn unlock_account_owned_by_another_program_is_rejected() {
+        let owner = Keypair::new();
+        let unlock_pda = unlock_pda(&owner);
+        let mut account = blank_unlock_state(AccountType::UnlockStateAccount);
+        account.owner = Pubkey::new_unique();
+        let context = context(&owner, MockRpc::default().with_account(unlock_pda, account));
+
+        let err = context.finalize_unlock(&unlock_pda).await.unwrap_err();
+        assert!(matches!(err, WalletError::InvalidAccountData { .. }), "{:?}", err);
+    }
+}
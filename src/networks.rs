@@ -0,0 +1,46 @@
+use crate::{MINT_ADDRESS, RPC_URL, VM_AUTHORITY, VM_STATE_ACCOUNT};
+
+/// Known cluster settings. Clusters without a known Code VM deployment only
+/// provide an RPC URL; the VM addresses must then be supplied explicitly.
+pub struct NetworkPreset {
+    pub name: &'static str,
+    pub rpc_url: &'static str,
+    pub mint: Option<&'static str>,
+    pub vm_state: Option<&'static str>,
+    pub vm_authority: Option<&'static str>,
+    /// Genesis hash identifying the cluster, checked by `doctor`
+    pub genesis_hash: Option<&'static str>,
+}
+
+pub const DEFAULT_NETWORK: &str = "mainnet";
+
+pub const NETWORKS: &[NetworkPreset] = &[
+    NetworkPreset {
+        name: "mainnet",
+        rpc_url: RPC_URL,
+        mint: Some(MINT_ADDRESS),
+        vm_state: Some(VM_STATE_ACCOUNT),
+        vm_authority: Some(VM_AUTHORITY),
+        genesis_hash: Some("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+    },
+    NetworkPreset {
+        name: "devnet",
+        rpc_url: "https://api.devnet.solana.com",
+        mint: None,
+        vm_state: None,
+        vm_authority: None,
+        genesis_hash: Some("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+    },
+    NetworkPreset {
+        name: "testnet",
+        rpc_url: "https://api.testnet.solana.com",
+        mint: None,
+        vm_state: None,
+        vm_authority: None,
+        genesis_hash: Some("4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY"),
+    },
+];
+
+pub fn lookup(name: &str) -> Option<&'static NetworkPreset> {
+    NETWORKS.iter().find(|preset| preset.name == name)
+}
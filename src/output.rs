@@ -0,0 +1,230 @@
+use env_logger::Target;
+use log::{Level, LevelFilter};
+use owo_colors::{OwoColorize, Stream};
+use serde::{Serialize, Serializer};
+use std::{
+    fmt::Display,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+use crate::cli::Explorer;
+use crate::error::WalletError;
+
+static JSON_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json_mode(enabled: bool) {
+    JSON_MODE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn json_mode() -> bool {
+    JSON_MODE.load(Ordering::Relaxed)
+}
+
+/// Turns colors off for `--no-color` and `--json`. Otherwise they follow the
+/// terminal: on for a TTY, off when piped or when `NO_COLOR` is set.
+pub fn init_color(no_color: bool) {
+    if no_color || json_mode() {
+        owo_colors::set_override(false);
+    }
+}
+
+/// Green text for a step that succeeded.
+pub fn success(message: impl Display) -> String {
+    message.if_supports_color(Stream::Stdout, |text| text.green()).to_string()
+}
+
+/// Routes log output: plain status lines on stdout for humans, or stderr in
+/// `--json` mode so stdout only carries the JSON result. `-v`/`-q` adjust the
+/// level of this crate's logs; `RUST_LOG` overrides everything.
+pub fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) if json_mode() => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+
+    let mut builder = env_logger::Builder::new();
+    builder
+        .filter_level(LevelFilter::Warn)
+        .filter_module(env!("CARGO_CRATE_NAME"), level)
+        .target(if json_mode() { Target::Stderr } else { Target::Stdout })
+        .format(|buf, record| match record.level() {
+            Level::Info => writeln!(buf, "{}", record.args()),
+            Level::Warn => {
+                let line = format!("[WARN] {}", record.args());
+                writeln!(buf, "{}", line.if_supports_color(Stream::Stdout, |text| text.yellow()))
+            }
+            Level::Error => {
+                let line = format!("[ERROR] {}", record.args());
+                writeln!(buf, "{}", line.if_supports_color(Stream::Stdout, |text| text.red()))
+            }
+            level => writeln!(buf, "[{}] {}", level, record.args()),
+        });
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+    builder.init();
+}
+
+static EXPLORER: OnceLock<(Explorer, String)> = OnceLock::new();
+
+/// Picks the explorer and cluster used for transaction links; defaults to
+/// Solscan on mainnet when never called.
+pub fn set_explorer(explorer: Explorer, network: &str) {
+    let _ = EXPLORER.set((explorer, network.to_string()));
+}
+
+/// Transaction URL templates and the `cluster` query value each explorer uses
+/// per network; mainnet needs no query.
+const EXPLORER_TEMPLATES: &[(Explorer, &str, &[(&str, &str)])] = &[
+    (Explorer::Solscan, "https://solscan.io/tx/", &[("devnet", "devnet"), ("testnet", "testnet")]),
+    (Explorer::Solanafm, "https://solana.fm/tx/", &[("devnet", "devnet-solana"), ("testnet", "testnet-solana")]),
+    (Explorer::Explorer, "https://explorer.solana.com/tx/", &[("devnet", "devnet"), ("testnet", "testnet")]),
+];
+
+pub fn explorer_url(signature: &str) -> String {
+    let (explorer, network) = EXPLORER.get().map_or((Explorer::Solscan, "mainnet"), |(e, n)| (*e, n.as_str()));
+    let (_, base, clusters) = EXPLORER_TEMPLATES
+        .iter()
+        .find(|(e, _, _)| *e == explorer)
+        .expect("every explorer has a template");
+
+    match clusters.iter().find(|(name, _)| *name == network) {
+        Some((_, cluster)) => format!("{}{}?cluster={}", base, signature, cluster),
+        None => format!("{}{}", base, signature),
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TxRecord {
+    pub label: String,
+    pub signature: String,
+    pub explorer_url: String,
+    #[serde(flatten)]
+    pub result: TransactionResult,
+    #[serde(flatten)]
+    pub confirmation: Confirmation,
+}
+
+/// How final a transaction was when checked once more after confirming.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Confirmation {
+    /// One of "processed", "confirmed" or "finalized"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmation_status: Option<&'static str>,
+    /// Confirmations so far; absent once finalized
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<usize>,
+}
+
+/// What the confirmed transaction's metadata says it did, fetched once after
+/// confirmation. Every field is empty when the RPC does not report it.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TransactionResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_lamports: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute_units: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pre_token_balances: Vec<TokenBalance>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_token_balances: Vec<TokenBalance>,
+}
+
+/// A token account balance before or after a transaction.
+#[derive(Serialize, Debug, Clone)]
+pub struct TokenBalance {
+    /// Index of the token account in the transaction's account keys
+    pub account_index: u8,
+    pub mint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Raw amount in base units
+    pub amount: String,
+    pub decimals: u8,
+}
+
+/// Machine-readable summary of a run, emitted once in `--json` mode.
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub owner: String,
+    pub unlock_pda: String,
+    pub transactions: Vec<TxRecord>,
+    /// Fees paid by the confirmed transactions whose fee is known
+    pub fees_lamports: u64,
+    pub duration_secs: f64,
+}
+
+/// Where an owner's timelock stands, as printed by `status`.
+#[derive(Serialize, Debug)]
+pub struct StatusReport {
+    pub owner: String,
+    pub timelock_address: String,
+    pub unlock_pda: String,
+    /// One of "locked", "waiting" or "unlocked"
+    pub state: &'static str,
+    pub unlock_at: Option<i64>,
+    pub seconds_remaining: Option<i64>,
+    pub current_slot: u64,
+    /// Slot the timelock should pass at, from the recent average slot time
+    pub estimated_unlock_slot: Option<u64>,
+    pub next_action: &'static str,
+}
+
+/// Outcome for one owner of an `--owners-dir` batch.
+#[derive(Serialize, Debug)]
+pub struct OwnerResult {
+    pub keyfile: String,
+    /// Missing when the keyfile itself failed to load
+    pub owner: Option<String>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub transactions: Vec<TxRecord>,
+}
+
+/// Summary of an `--owners-dir` batch, emitted once in `--json` mode.
+#[derive(Serialize, Debug)]
+pub struct BatchReport {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub fees_lamports: u64,
+    /// Wall-clock time for the whole batch
+    pub duration_secs: f64,
+    pub owners: Vec<OwnerResult>,
+}
+
+pub fn total_fees(transactions: &[TxRecord]) -> u64 {
+    transactions.iter().filter_map(|tx| tx.result.fee_lamports).sum()
+}
+
+#[derive(Serialize)]
+struct ErrorReport<'a> {
+    error: String,
+    kind: &'a str,
+}
+
+/// Serializes any `Display` value (pubkeys, signatures) as a plain string.
+pub fn as_string<T: std::fmt::Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+pub fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Failed to serialize output: {}", e),
+    }
+}
+
+pub fn print_json_error(err: &WalletError) {
+    print_json(&ErrorReport {
+        error: err.to_string(),
+        kind: err.kind(),
+    });
+}
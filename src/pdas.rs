@@ -0,0 +1,173 @@
+use code_vm_api::prelude::*;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// SPL Token and Associated Token Account programs, for deriving an owner's
+/// token account without depending on the SPL crates.
+const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+/// Every address the unlock flow derives for one owner.
+#[derive(Serialize, Debug, Clone)]
+pub struct UnlockPdas {
+    #[serde(serialize_with = "crate::output::as_string")]
+    pub owner: Pubkey,
+    #[serde(serialize_with = "crate::output::as_string")]
+    pub timelock_address: Pubkey,
+    pub timelock_bump: u8,
+    #[serde(serialize_with = "crate::output::as_string")]
+    pub unlock_pda: Pubkey,
+    pub unlock_bump: u8,
+}
+
+/// Inputs of the virtual timelock derivation. Every pubkey has the same
+/// type, so they are passed by name: swapping two would still derive a
+/// valid-looking, but wrong, address.
+#[derive(Debug, Clone, Copy)]
+pub struct TimelockParams {
+    pub mint: Pubkey,
+    pub vm_authority: Pubkey,
+    pub owner: Pubkey,
+    /// Lock duration in days
+    pub lock_duration: u8,
+}
+
+/// Inputs of the unlock PDA derivation, named for the same reason.
+#[derive(Debug, Clone, Copy)]
+pub struct UnlockParams {
+    pub owner: Pubkey,
+    pub timelock_address: Pubkey,
+    pub vm_state: Pubkey,
+}
+
+pub fn derive_timelock(params: &TimelockParams) -> (Pubkey, u8) {
+    find_virtual_timelock_address(&params.mint, &params.vm_authority, &params.owner, params.lock_duration)
+}
+
+pub fn derive_unlock(params: &UnlockParams) -> (Pubkey, u8) {
+    find_unlock_address(&params.owner, &params.timelock_address, &params.vm_state)
+}
+
+/// The owner's associated token account for `mint`.
+pub fn derive_associated_token_account(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let token_program = Pubkey::from_str(TOKEN_PROGRAM_ID).expect("valid token program id");
+    let program_id = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).expect("valid associated token program id");
+    Pubkey::find_program_address(&[owner.as_ref(), token_program.as_ref(), mint.as_ref()], &program_id).0
+}
+
+pub fn derive_unlock_pdas(timelock: &TimelockParams, vm_state: &Pubkey) -> UnlockPdas {
+    let (timelock_address, timelock_bump) = derive_timelock(timelock);
+    let (unlock_pda, unlock_bump) = derive_unlock(&UnlockParams {
+        owner: timelock.owner,
+        timelock_address,
+        vm_state: *vm_state,
+    });
+
+    UnlockPdas {
+        owner: timelock.owner,
+        timelock_address,
+        timelock_bump,
+        unlock_pda,
+        unlock_bump,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn timelock_params() -> TimelockParams {
+        TimelockParams {
+            mint: key(1),
+            vm_authority: key(2),
+            owner: key(3),
+            lock_duration: 21,
+        }
+    }
+
+    /// (owner, timelock, vm_state) seed bytes with the unlock PDA and bump
+    /// expected under the VM program.
+    const UNLOCK_VECTORS: [(u8, u8, u8, &str, u8); 2] = [
+        (1, 2, 3, "3AdWdyFJZgH8SujSo8ZeQ4DUjZFZNB9aac4rNxCdEatZ", 255),
+        (7, 8, 9, "8xdo14XAerKvxDyEo99jGdczjkEgfd3xdq7Json9yp7y", 254),
+    ];
+
+    #[test]
+    fn unlock_address_matches_known_vectors() {
+        for (owner, timelock, vm_state, expected, bump) in UNLOCK_VECTORS {
+            let expected = (Pubkey::from_str(expected).unwrap(), bump);
+            assert_eq!(find_unlock_address(&key(owner), &key(timelock), &key(vm_state)), expected);
+            let params = UnlockParams {
+                owner: key(owner),
+                timelock_address: key(timelock),
+                vm_state: key(vm_state),
+            };
+            assert_eq!(derive_unlock(&params), expected);
+        }
+    }
+
+    #[test]
+    fn swapped_unlock_inputs_derive_other_addresses() {
+        let params = UnlockParams {
+            owner: key(1),
+            timelock_address: key(2),
+            vm_state: key(3),
+        };
+        let expected = derive_unlock(&params).0;
+        let swaps = [
+            UnlockParams { owner: params.timelock_address, timelock_address: params.owner, ..params },
+            UnlockParams { owner: params.vm_state, vm_state: params.owner, ..params },
+            UnlockParams { timelock_address: params.vm_state, vm_state: params.timelock_address, ..params },
+        ];
+        for swapped in swaps {
+            assert_ne!(derive_unlock(&swapped).0, expected, "{:?}", swapped);
+        }
+    }
+
+    #[test]
+    fn derive_timelock_is_deterministic() {
+        assert_eq!(derive_timelock(&timelock_params()), derive_timelock(&timelock_params()));
+    }
+
+    #[test]
+    fn swapped_timelock_inputs_derive_other_addresses() {
+        let params = timelock_params();
+        let expected = derive_timelock(&params).0;
+        let swaps = [
+            TimelockParams { mint: params.vm_authority, vm_authority: params.mint, ..params },
+            TimelockParams { mint: params.owner, owner: params.mint, ..params },
+            TimelockParams { vm_authority: params.owner, owner: params.vm_authority, ..params },
+            TimelockParams { lock_duration: params.lock_duration + 1, ..params },
+        ];
+        for swapped in swaps {
+            assert_ne!(derive_timelock(&swapped).0, expected, "{:?}", swapped);
+        }
+    }
+
+    #[test]
+    fn unlock_pdas_chain_the_timelock_into_the_unlock() {
+        let params = timelock_params();
+        let vm_state = key(4);
+        let pdas = derive_unlock_pdas(&params, &vm_state);
+        assert_eq!((pdas.timelock_address, pdas.timelock_bump), derive_timelock(&params));
+        let unlock = derive_unlock(&UnlockParams {
+            owner: params.owner,
+            timelock_address: pdas.timelock_address,
+            vm_state,
+        });
+        assert_eq!((pdas.unlock_pda, pdas.unlock_bump), unlock);
+        assert_eq!(pdas.owner, params.owner);
+    }
+
+    #[test]
+    fn associated_token_account_matches_known_vector() {
+        let mint = Pubkey::from_str(crate::MINT_ADDRESS).unwrap();
+        let expected = Pubkey::from_str("51UExkwyyJSk7zqhjn4g4dfNMvehY4WZb6T79bwZP74y").unwrap();
+        assert_eq!(derive_associated_token_account(&key(1), &mint), expected);
+    }
+}
@@ -0,0 +1,87 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+};
+
+use crate::error::WalletError;
+
+/// One completed unlock step, stored as a line of JSON.
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    owner: String,
+    unlock_pda: String,
+    step: String,
+    /// `unlock_at` of the unlock a finalize step completed, which tells one
+    /// unlock cycle of the same PDA from the next
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unlock_at: Option<i64>,
+    signature: String,
+    timestamp: i64,
+}
+
+/// Returns the signature logged for (owner, unlock PDA, step, unlock_at), so
+/// a later run can skip a step it already sent without asking the cluster.
+/// Only the most recent entry for the owner and unlock PDA counts: once a
+/// later step is logged, the earlier one belongs to a finished cycle.
+pub fn lookup(
+    path: &str,
+    owner: &Pubkey,
+    unlock_pda: &Pubkey,
+    step: &str,
+    unlock_at: Option<i64>,
+) -> Result<Option<String>, WalletError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let (owner, unlock_pda) = (owner.to_string(), unlock_pda.to_string());
+    let mut latest = None;
+    for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        // A crash mid-write can leave a truncated last line; it never matched anything.
+        let entry: Entry = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Ignoring unreadable line {} of {}: {}", number + 1, path, e);
+                continue;
+            }
+        };
+        if entry.owner == owner && entry.unlock_pda == unlock_pda {
+            latest = Some(entry);
+        }
+    }
+    Ok(latest
+        .filter(|entry| entry.step == step && entry.unlock_at == unlock_at)
+        .map(|entry| entry.signature))
+}
+
+/// Appends a confirmed step as a single write, so concurrent runs never
+/// interleave partial lines, and syncs it to disk before returning.
+pub fn append(
+    path: &str,
+    owner: &Pubkey,
+    unlock_pda: &Pubkey,
+    step: &str,
+    unlock_at: Option<i64>,
+    signature: &Signature,
+) -> Result<(), WalletError> {
+    let entry = Entry {
+        owner: owner.to_string(),
+        unlock_pda: unlock_pda.to_string(),
+        step: step.to_string(),
+        unlock_at,
+        signature: signature.to_string(),
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    let line = serde_json::to_string(&entry)
+        .map_err(|e| WalletError::Config(format!("failed to serialize processed log entry: {}", e)))?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n", line).as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
@@ -0,0 +1,505 @@
+use async_trait::async_trait;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig},
+    rpc_request::RpcError,
+    rpc_response::{RpcPerfSample, RpcSimulateTransactionResult, RpcVersionInfo},
+};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, TransactionStatus, UiTransactionEncoding,
+};
+use std::{
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+/// A prioritised list of RPC endpoints. Calls go to the endpoint that last
+/// succeeded and fail over to the next one on transport errors only.
+pub struct RpcPool {
+    clients: Vec<RpcClient>,
+    urls: Vec<String>,
+    active: AtomicUsize,
+    retry: RetryPolicy,
+}
+
+impl RpcPool {
+    /// `timeout` bounds each HTTP request; `None` keeps the client default.
+    pub fn new(
+        urls: Vec<String>,
+        commitment: CommitmentConfig,
+        timeout: Option<Duration>,
+        retry: RetryPolicy,
+    ) -> Self {
+        let clients = urls
+            .iter()
+            .map(|url| match timeout {
+                Some(timeout) => RpcClient::new_with_timeout_and_commitment(url.clone(), timeout, commitment),
+                None => RpcClient::new_with_commitment(url.clone(), commitment),
+            })
+            .collect();
+        Self {
+            clients,
+            urls,
+            active: AtomicUsize::new(0),
+            retry,
+        }
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.clients[self.active.load(Ordering::Relaxed)].commitment()
+    }
+
+    /// Runs `f` against the current endpoint (with retries), moving on to the
+    /// next endpoint if it keeps failing at the transport level.
+    pub async fn call<'a, T, F, Fut>(&'a self, f: F) -> Result<T, ClientError>
+    where
+        F: Fn(&'a RpcClient) -> Fut,
+        Fut: Future<Output = Result<T, ClientError>> + 'a,
+    {
+        let start = self.active.load(Ordering::Relaxed);
+        let mut last_err = None;
+
+        for offset in 0..self.clients.len() {
+            let index = (start + offset) % self.clients.len();
+            let client = &self.clients[index];
+
+            match with_retry(&self.retry, || f(client)).await {
+                Ok(value) => {
+                    if index != start {
+                        self.active.store(index, Ordering::Relaxed);
+                        log::warn!("Switched to RPC endpoint {}", self.urls[index]);
+                    }
+                    return Ok(value);
+                }
+                Err(err) if is_transient(&err) => {
+                    log::warn!("RPC endpoint {} unavailable: {}", self.urls[index], err);
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(last_err.expect("RPC pool has at least one endpoint"))
+    }
+}
+
+/// The RPC calls the unlock flow depends on. `UnlockContext` only talks to the
+/// cluster through this trait, so it can run against a mock in place of
+/// `RpcPool`.
+#[async_trait]
+pub trait VmRpc: Send + Sync {
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Option<Account>>;
+
+    /// Reads an account at `commitment`, with the slot the read was served at.
+    async fn get_account_with_slot(
+        &self,
+        address: &Pubkey,
+        commitment: CommitmentConfig,
+    ) -> ClientResult<(u64, Option<Account>)>;
+
+    /// Fetches several accounts in one round trip, in the order given, with
+    /// the slot the read was served at.
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> ClientResult<(u64, Vec<Option<Account>>)>;
+
+    /// Latest blockhash and the last block height at which it is valid.
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)>;
+
+    async fn get_block_height(&self) -> ClientResult<u64>;
+
+    /// Broadcasts `tx`, with preflight as `config` asks.
+    async fn send_transaction(&self, tx: &VersionedTransaction, config: RpcSendTransactionConfig) -> ClientResult<Signature>;
+
+    async fn get_signature_status(&self, signature: &Signature) -> ClientResult<Option<TransactionStatus>>;
+
+    async fn simulate_transaction(&self, tx: &VersionedTransaction) -> ClientResult<RpcSimulateTransactionResult>;
+
+    /// Fee in lamports the cluster would charge for the message.
+    async fn get_fee_for_message(&self, message: &VersionedMessage) -> ClientResult<u64>;
+
+    /// Fetches a confirmed transaction with its status metadata.
+    async fn get_transaction(&self, signature: &Signature) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta>;
+
+    /// Succeeds if the node reports itself healthy.
+    async fn get_health(&self) -> ClientResult<()>;
+
+    async fn get_version(&self) -> ClientResult<RpcVersionInfo>;
+
+    /// Identifies the cluster the endpoint serves.
+    async fn get_genesis_hash(&self) -> ClientResult<Hash>;
+
+    async fn get_balance(&self, address: &Pubkey) -> ClientResult<u64>;
+
+    /// Lamports an account with `data_len` bytes needs to be rent-exempt.
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64>;
+
+    /// The most recent performance samples, newest first.
+    async fn get_recent_performance_samples(&self, limit: usize) -> ClientResult<Vec<RpcPerfSample>>;
+}
+
+#[async_trait]
+impl VmRpc for RpcPool {
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Option<Account>> {
+        let commitment = self.commitment();
+        Ok(self
+            .call(|client| client.get_account_with_commitment(address, commitment))
+            .await?
+            .value)
+    }
+
+    async fn get_account_with_slot(
+        &self,
+        address: &Pubkey,
+        commitment: CommitmentConfig,
+    ) -> ClientResult<(u64, Option<Account>)> {
+        let response = self
+            .call(|client| client.get_account_with_commitment(address, commitment))
+            .await?;
+        Ok((response.context.slot, response.value))
+    }
+
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> ClientResult<(u64, Vec<Option<Account>>)> {
+        let commitment = self.commitment();
+        let response = self
+            .call(|client| client.get_multiple_accounts_with_commitment(addresses, commitment))
+            .await?;
+        Ok((response.context.slot, response.value))
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)> {
+        let commitment = self.commitment();
+        self.call(|client| client.get_latest_blockhash_with_commitment(commitment)).await
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        let commitment = self.commitment();
+        self.call(|client| client.get_block_height_with_commitment(commitment)).await
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction, config: RpcSendTransactionConfig) -> ClientResult<Signature> {
+        // Matches RpcClient::send_transaction, which preflights at the client commitment.
+        let config = RpcSendTransactionConfig {
+            preflight_commitment: config.preflight_commitment.or(Some(self.commitment().commitment)),
+            ..config
+        };
+        self.call(|client| client.send_transaction_with_config(tx, config)).await
+    }
+
+    async fn get_signature_status(&self, signature: &Signature) -> ClientResult<Option<TransactionStatus>> {
+        let signatures = [*signature];
+        Ok(self
+            .call(|client| client.get_signature_statuses(&signatures))
+            .await?
+            .value
+            .into_iter()
+            .next()
+            .flatten())
+    }
+
+    async fn simulate_transaction(&self, tx: &VersionedTransaction) -> ClientResult<RpcSimulateTransactionResult> {
+        Ok(self.call(|client| client.simulate_transaction(tx)).await?.value)
+    }
+
+    async fn get_fee_for_message(&self, message: &VersionedMessage) -> ClientResult<u64> {
+        match message {
+            VersionedMessage::Legacy(message) => self.call(|client| client.get_fee_for_message(message)).await,
+            VersionedMessage::V0(message) => self.call(|client| client.get_fee_for_message(message)).await,
+        }
+    }
+
+    async fn get_transaction(&self, signature: &Signature) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        self.call(|client| client.get_transaction_with_config(signature, config)).await
+    }
+
+    async fn get_health(&self) -> ClientResult<()> {
+        self.call(|client| client.get_health()).await
+    }
+
+    async fn get_version(&self) -> ClientResult<RpcVersionInfo> {
+        self.call(|client| client.get_version()).await
+    }
+
+    async fn get_genesis_hash(&self) -> ClientResult<Hash> {
+        self.call(|client| client.get_genesis_hash()).await
+    }
+
+    async fn get_balance(&self, address: &Pubkey) -> ClientResult<u64> {
+        let commitment = self.commitment();
+        Ok(self
+            .call(|client| client.get_balance_with_commitment(address, commitment))
+            .await?
+            .value)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        self.call(|client| client.get_minimum_balance_for_rent_exemption(data_len)).await
+    }
+
+    async fn get_recent_performance_samples(&self, limit: usize) -> ClientResult<Vec<RpcPerfSample>> {
+        self.call(|client| client.get_recent_performance_samples(Some(limit))).await
+    }
+}
+
+/// Runs an RPC call, retrying transient failures with exponential backoff and
+/// jitter. Deterministic failures are returned immediately.
+pub async fn with_retry<T, F, Fut>(policy: &RetryPolicy, mut f: F) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < policy.max_retries && is_transient(&err) => {
+                let delay = backoff_delay(policy.base_delay, attempt);
+                log::warn!("RPC call failed ({}), retrying in {}ms", err, delay.as_millis());
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Whether repeating the same request could succeed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Timeouts, connection failures, rate limiting and unhealthy nodes
+    Transient,
+    /// Program errors, failed signature checks and rejected requests, which
+    /// fail the same way every time
+    Terminal,
+}
+
+// JSON-RPC server error codes a healthy node would not return.
+const BLOCK_NOT_AVAILABLE: i64 = -32004;
+const NODE_UNHEALTHY: i64 = -32005;
+const MIN_CONTEXT_SLOT_NOT_REACHED: i64 = -32016;
+
+/// Classifies an RPC failure for the retry and failover logic. Transaction
+/// errors are always terminal here, `BlockhashNotFound` included: resending
+/// the same bytes cannot help, and `process_tx` re-signs expired
+/// transactions itself.
+pub fn classify(err: &ClientError) -> ErrorClass {
+    match err.kind() {
+        ClientErrorKind::Io(_) => ErrorClass::Transient,
+        // No status means the request never got an HTTP response.
+        ClientErrorKind::Reqwest(err) => match err.status().map(|status| status.as_u16()) {
+            None | Some(429) | Some(500..=599) => ErrorClass::Transient,
+            Some(_) => ErrorClass::Terminal,
+        },
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => ErrorClass::Transient,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+            if matches!(*code, BLOCK_NOT_AVAILABLE | NODE_UNHEALTHY | MIN_CONTEXT_SLOT_NOT_REACHED) =>
+        {
+            ErrorClass::Transient
+        }
+        _ => ErrorClass::Terminal,
+    }
+}
+
+fn is_transient(err: &ClientError) -> bool {
+    classify(err) == ErrorClass::Transient
+}
+
+fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    let exp = base.saturating_mul(2u32.saturating_pow(attempt));
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    let jitter_ms = nanos % (base.as_millis() as u64 / 2 + 1);
+    exp + Duration::from_millis(jitter_ms)
+}
+
+/// In-memory `VmRpc` for tests: serves canned accounts and fails every call
+/// that would need a live cluster.
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use std::{collections::HashMap, sync::Arc};
+
+    #[derive(Default)]
+    pub struct MockRpc {
+        accounts: HashMap<Pubkey, Account>,
+        slot: u64,
+        /// Single-account reads served so far
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl MockRpc {
+        pub fn with_account(mut self, address: Pubkey, account: Account) -> Self {
+            self.accounts.insert(address, account);
+            self
+        }
+
+        /// Counter of single-account reads, still readable once the mock is boxed.
+        pub fn reads(&self) -> Arc<AtomicUsize> {
+            self.reads.clone()
+        }
+    }
+
+    fn not_mocked<T>(call: &str) -> ClientResult<T> {
+        Err(ClientErrorKind::Custom(format!("{} is not mocked", call)).into())
+    }
+
+    #[async_trait]
+    impl VmRpc for MockRpc {
+        async fn get_account(&self, address: &Pubkey) -> ClientResult<Option<Account>> {
+            Ok(self.accounts.get(address).cloned())
+        }
+
+        async fn get_account_with_slot(
+            &self,
+            address: &Pubkey,
+            _commitment: CommitmentConfig,
+        ) -> ClientResult<(u64, Option<Account>)> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok((self.slot, self.accounts.get(address).cloned()))
+        }
+
+        async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> ClientResult<(u64, Vec<Option<Account>>)> {
+            Ok((self.slot, addresses.iter().map(|address| self.accounts.get(address).cloned()).collect()))
+        }
+
+        async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)> {
+            not_mocked("get_latest_blockhash")
+        }
+
+        async fn get_block_height(&self) -> ClientResult<u64> {
+            not_mocked("get_block_height")
+        }
+
+        async fn send_transaction(&self, _tx: &VersionedTransaction, _config: RpcSendTransactionConfig) -> ClientResult<Signature> {
+            not_mocked("send_transaction")
+        }
+
+        async fn get_signature_status(&self, _signature: &Signature) -> ClientResult<Option<TransactionStatus>> {
+            not_mocked("get_signature_status")
+        }
+
+        async fn simulate_transaction(&self, _tx: &VersionedTransaction) -> ClientResult<RpcSimulateTransactionResult> {
+            not_mocked("simulate_transaction")
+        }
+
+        async fn get_fee_for_message(&self, _message: &VersionedMessage) -> ClientResult<u64> {
+            not_mocked("get_fee_for_message")
+        }
+
+        async fn get_transaction(&self, _signature: &Signature) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+            not_mocked("get_transaction")
+        }
+
+        async fn get_health(&self) -> ClientResult<()> {
+            not_mocked("get_health")
+        }
+
+        async fn get_version(&self) -> ClientResult<RpcVersionInfo> {
+            not_mocked("get_version")
+        }
+
+        async fn get_genesis_hash(&self) -> ClientResult<Hash> {
+            not_mocked("get_genesis_hash")
+        }
+
+        async fn get_balance(&self, _address: &Pubkey) -> ClientResult<u64> {
+            not_mocked("get_balance")
+        }
+
+        async fn get_minimum_balance_for_rent_exemption(&self, _data_len: usize) -> ClientResult<u64> {
+            not_mocked("get_minimum_balance_for_rent_exemption")
+        }
+
+        async fn get_recent_performance_samples(&self, _limit: usize) -> ClientResult<Vec<RpcPerfSample>> {
+            not_mocked("get_recent_performance_samples")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcResponseErrorData;
+    use solana_sdk::transaction::TransactionError;
+
+    fn http_error(status: u16) -> ClientError {
+        let response = http::Response::builder().status(status).body("").unwrap();
+        let err = reqwest::Response::from(response).error_for_status().unwrap_err();
+        ClientErrorKind::Reqwest(err).into()
+    }
+
+    fn response_error(code: i64, data: RpcResponseErrorData) -> ClientError {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code,
+            message: "rpc error".to_string(),
+            data,
+        })
+        .into()
+    }
+
+    #[test]
+    fn io_errors_are_transient() {
+        let err: ClientError = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset").into();
+        assert_eq!(classify(&err), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn rate_limits_and_server_errors_are_transient() {
+        assert_eq!(classify(&http_error(429)), ErrorClass::Transient);
+        assert_eq!(classify(&http_error(503)), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn other_client_errors_are_terminal() {
+        assert_eq!(classify(&http_error(400)), ErrorClass::Terminal);
+        assert_eq!(classify(&http_error(403)), ErrorClass::Terminal);
+    }
+
+    #[test]
+    fn unhealthy_node_is_transient() {
+        let err = response_error(NODE_UNHEALTHY, RpcResponseErrorData::Empty);
+        assert_eq!(classify(&err), ErrorClass::Transient);
+        let err = response_error(MIN_CONTEXT_SLOT_NOT_REACHED, RpcResponseErrorData::Empty);
+        assert_eq!(classify(&err), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn transaction_errors_are_terminal() {
+        let err: ClientError = ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound).into();
+        assert_eq!(classify(&err), ErrorClass::Terminal);
+    }
+
+    #[test]
+    fn preflight_failures_are_terminal() {
+        let simulation: RpcSimulateTransactionResult = serde_json::from_value(serde_json::json!({
+            "err": { "InstructionError": [0, { "Custom": 1 }] },
+            "logs": ["Program failed"],
+        }))
+        .unwrap();
+        // -32002: JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE
+        let err = response_error(-32002, RpcResponseErrorData::SendTransactionPreflightFailure(simulation));
+        assert_eq!(classify(&err), ErrorClass::Terminal);
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::error::WalletError;
+use crate::txfile::{decode_base64_tx, encode_base64_tx};
+
+/// Body POSTed to the sponsor: the owner-signed transaction with the fee
+/// payer's signature left blank, as base64 bincode.
+#[derive(Serialize)]
+struct SponsorRequest {
+    transaction: String,
+}
+
+/// The sponsor's reply: the same transaction with the fee payer signature filled in.
+#[derive(Deserialize)]
+struct SponsorResponse {
+    transaction: String,
+}
+
+/// Sends a partially signed transaction to the sponsor at `url` and returns
+/// it fully signed. Rejects replies whose message differs from what we signed
+/// or whose signatures do not verify.
+pub async fn cosign(url: &str, tx: &VersionedTransaction) -> Result<VersionedTransaction, WalletError> {
+    let sponsor_error = |reason: String| WalletError::Sponsor(reason);
+    let request = SponsorRequest {
+        transaction: encode_base64_tx(tx)?,
+    };
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&request)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| sponsor_error(e.to_string()))?
+        .json::<SponsorResponse>()
+        .await
+        .map_err(|e| sponsor_error(format!("invalid response: {}", e)))?;
+
+    let signed = decode_base64_tx(&response.transaction).map_err(|e| sponsor_error(e.to_string()))?;
+    if signed.message != tx.message {
+        return Err(sponsor_error("returned transaction does not match the one sent".to_string()));
+    }
+    if signed.verify_with_results().iter().any(|valid| !valid) {
+        return Err(sponsor_error("returned transaction is not fully signed".to_string()));
+    }
+    Ok(signed)
+}
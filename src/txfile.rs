@@ -0,0 +1,97 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::transaction::VersionedTransaction;
+use std::fs;
+
+use crate::cli::TxEncoding;
+use crate::error::WalletError;
+
+/// Writes a signed transaction as a single line of base64 or base58 text.
+pub fn write_tx(path: &str, tx: &VersionedTransaction, encoding: TxEncoding) -> Result<(), WalletError> {
+    let text = match encoding {
+        TxEncoding::Base64 => encode_base64_tx(tx)?,
+        TxEncoding::Base58 => bs58::encode(serialize_tx(tx)?).into_string(),
+    };
+    fs::write(path, text + "\n")?;
+    Ok(())
+}
+
+/// Reads a transaction written by `write_tx`.
+pub fn read_tx(path: &str, encoding: TxEncoding) -> Result<VersionedTransaction, WalletError> {
+    let invalid = |reason: String| WalletError::Config(format!("invalid transaction file {}: {}", path, reason));
+    let text = fs::read_to_string(path)?;
+    let bytes = match encoding {
+        TxEncoding::Base64 => STANDARD.decode(text.trim()).map_err(|e| invalid(e.to_string()))?,
+        TxEncoding::Base58 => bs58::decode(text.trim()).into_vec().map_err(|e| invalid(e.to_string()))?,
+    };
+    let tx: VersionedTransaction = bincode::deserialize(&bytes).map_err(|e| invalid(e.to_string()))?;
+    // An empty signature list would pass verification vacuously.
+    let required = usize::from(tx.message.header().num_required_signatures);
+    if tx.signatures.is_empty() || tx.signatures.len() != required {
+        return Err(invalid(format!(
+            "has {} signatures, but its message requires {}",
+            tx.signatures.len(),
+            required
+        )));
+    }
+    if tx.verify_with_results().iter().any(|valid| !valid) {
+        return Err(invalid("signature verification failed".to_string()));
+    }
+    Ok(tx)
+}
+
+fn serialize_tx(tx: &VersionedTransaction) -> Result<Vec<u8>, WalletError> {
+    bincode::serialize(tx).map_err(|e| WalletError::TransactionBuild(e.to_string()))
+}
+
+pub fn encode_base64_tx(tx: &VersionedTransaction) -> Result<String, WalletError> {
+    Ok(STANDARD.encode(serialize_tx(tx)?))
+}
+
+pub fn decode_base64_tx(text: &str) -> Result<VersionedTransaction, WalletError> {
+    let invalid = |reason: String| WalletError::TransactionBuild(format!("invalid transaction encoding: {}", reason));
+    let bytes = STANDARD.decode(text.trim()).map_err(|e| invalid(e.to_string()))?;
+    bincode::deserialize(&bytes).map_err(|e| invalid(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        message::{Message, VersionedMessage},
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    fn transfer(payer: &Keypair) -> VersionedMessage {
+        let ix = system_instruction::transfer(&payer.pubkey(), &payer.pubkey(), 1);
+        VersionedMessage::Legacy(Message::new_with_blockhash(&[ix], Some(&payer.pubkey()), &Hash::new_unique()))
+    }
+
+    fn round_trip(name: &str, tx: &VersionedTransaction) -> Result<VersionedTransaction, WalletError> {
+        let path = std::env::temp_dir().join(format!("vm_wallet_{}_{}.tx", name, std::process::id()));
+        let path = path.to_str().unwrap();
+        write_tx(path, tx, TxEncoding::Base64).unwrap();
+        let read = read_tx(path, TxEncoding::Base64);
+        fs::remove_file(path).unwrap();
+        read
+    }
+
+    #[test]
+    fn signed_transaction_reads_back() {
+        let payer = Keypair::new();
+        let tx = VersionedTransaction::try_new(transfer(&payer), &[&payer]).unwrap();
+        assert_eq!(round_trip("signed", &tx).unwrap(), tx);
+    }
+
+    #[test]
+    fn rejects_transaction_without_signatures() {
+        let payer = Keypair::new();
+        let tx = VersionedTransaction {
+            signatures: Vec::new(),
+            message: transfer(&payer),
+        };
+        let err = round_trip("unsigned", &tx).unwrap_err();
+        assert!(matches!(&err, WalletError::Config(reason) if reason.contains("has 0 signatures")), "{:?}", err);
+    }
+}